@@ -1,9 +1,15 @@
 extern crate lucat_build;
 
+use std::env;
+use std::path::PathBuf;
+
 fn main() {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
     let res = lucat_build::configure()
         .build_client(true)
         .build_server(true)
+        .file_descriptor_set_path(out_dir.join("echo_descriptor.bin"))
         .compile(
             &["proto/echo/echo.proto"],
             &["proto/echo"],
@@ -12,4 +18,21 @@ fn main() {
         Ok(_res) => println!("{}", "OK"),
         Err(_err) => println!("{}", "Err"),
     };
+
+    lucat_build::configure()
+        .service_codec_path("greeter.Greeter", "lucat::codec::JsonCodec")
+        .method_codec_path("greeter.Greeter.SayHelloProto", "lucat::codec::ProstCodec")
+        .compile(
+            &["proto/greeter/greeter.proto"],
+            &["proto/greeter"],
+        )
+        .unwrap();
+
+    lucat_build::configure()
+        .service_codec_path("json.Mapping", "lucat::codec::JsonCodec")
+        .compile(
+            &["proto/json/json.proto"],
+            &["proto/json"],
+        )
+        .unwrap();
 }
@@ -0,0 +1,25 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use lucat::codec::error::Error;
+use lucat::codec::{BytesDecoder, Streaming};
+use lucat::{Body, Code, Status};
+
+async fn decode(frames: &'static [u8]) -> Status {
+    let body = Body::new(Some(Bytes::from_static(frames)));
+    let mut messages = Streaming::new(body, BytesDecoder);
+    messages.message().await.unwrap_err()
+}
+
+#[test]
+fn framing_errors_map_to_status_codes() {
+    runtime::block_on(async {
+        let compressed = decode(&[1, 0, 0, 0, 1, 0]).await;
+        assert_eq!(compressed.code(), Code::Unimplemented);
+
+        let truncated = decode(&[0, 0, 0, 0, 4, 1, 2]).await;
+        assert_eq!(truncated.code(), Code::Internal);
+    });
+
+    let too_large = Status::from(Error::TooLarge { len: 10, max: 5 });
+    assert_eq!(too_large.code(), Code::ResourceExhausted);
+}
@@ -0,0 +1,194 @@
+use cynthia::io::Timer;
+use cynthia::platform::lock::Barrier;
+use cynthia::runtime::{self, future};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+// Calls with data "block" meet the test on `entered` and wait until it lets
+// them go through `release`; "panic" panics; anything else is echoed at once.
+#[derive(Clone)]
+struct MyEcho {
+    calls: Arc<AtomicUsize>,
+    entered: Arc<Barrier>,
+    release: Arc<Barrier>,
+}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let r = request.into_inner();
+        match &r.data[..] {
+            b"block" => {
+                self.entered.wait().await;
+                self.release.wait().await;
+            }
+            b"panic" => panic!("handler gave up"),
+            _ => {}
+        }
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+struct Fixture {
+    endpoint: Endpoint,
+    calls: Arc<AtomicUsize>,
+    entered: Arc<Barrier>,
+    release: Arc<Barrier>,
+}
+
+fn serve(server: Server) -> Fixture {
+    let (endpoint, incoming) = Endpoint::in_memory();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let entered = Arc::new(Barrier::new(2));
+    let release = Arc::new(Barrier::new(2));
+
+    let echo = MyEcho {
+        calls: calls.clone(),
+        entered: entered.clone(),
+        release: release.clone(),
+    };
+    let mut server = server;
+    let router = server.register(EchoServer::new(echo));
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    Fixture {
+        endpoint,
+        calls,
+        entered,
+        release,
+    }
+}
+
+async fn say(endpoint: &Endpoint, data: &'static [u8]) -> Result<EchoResponse, Status> {
+    let mut client = EchoClient::new(endpoint.clone());
+    let request = EchoRequest {
+        data: data.to_vec(),
+        ..EchoRequest::default()
+    };
+    client
+        .say_echo(Request::new(request))
+        .await
+        .map(Response::into_inner)
+}
+
+#[test]
+fn calls_over_the_limit_are_rejected() {
+    runtime::block_on(async {
+        let fixture = serve(Server::builder().concurrency_limit(1));
+
+        let endpoint = fixture.endpoint.clone();
+        let blocked = runtime::spawn(async move { say(&endpoint, b"block").await });
+        fixture.entered.wait().await;
+
+        let status = say(&fixture.endpoint, b"fast").await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        fixture.release.wait().await;
+        assert_eq!(blocked.await.unwrap().data, b"block");
+        assert_eq!(say(&fixture.endpoint, b"fast").await.unwrap().data, b"fast");
+    });
+}
+
+#[test]
+fn queued_calls_time_out_as_unavailable() {
+    runtime::block_on(async {
+        let server = Server::builder()
+            .concurrency_limit(1)
+            .queue_timeout(Duration::from_millis(50));
+        let fixture = serve(server);
+
+        let endpoint = fixture.endpoint.clone();
+        let blocked = runtime::spawn(async move { say(&endpoint, b"block").await });
+        fixture.entered.wait().await;
+
+        let status = say(&fixture.endpoint, b"fast").await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+
+        fixture.release.wait().await;
+        assert_eq!(blocked.await.unwrap().data, b"block");
+    });
+}
+
+#[test]
+fn permits_are_released_when_handlers_panic() {
+    runtime::block_on(async {
+        let fixture = serve(Server::builder().concurrency_limit(1));
+
+        for _ in 0..3 {
+            let status = say(&fixture.endpoint, b"panic").await.unwrap_err();
+            assert_eq!(status.code(), Code::Internal);
+        }
+        assert_eq!(say(&fixture.endpoint, b"fast").await.unwrap().data, b"fast");
+    });
+}
+
+#[test]
+fn permits_are_released_when_calls_are_cancelled() {
+    runtime::block_on(async {
+        // queued calls wait long enough for the cancelled handler to go away,
+        // but not for one that is never released
+        let server = Server::builder()
+            .concurrency_limit(1)
+            .queue_timeout(Duration::from_secs(1));
+        let fixture = serve(server);
+
+        // the call is dropped, and the stream reset, once its handler runs
+        let call = async { Some(say(&fixture.endpoint, b"block").await) };
+        let entered = async {
+            fixture.entered.wait().await;
+            None
+        };
+        assert!(future::or(call, entered).await.is_none());
+
+        assert_eq!(say(&fixture.endpoint, b"fast").await.unwrap().data, b"fast");
+    });
+}
+
+#[test]
+fn streams_over_max_concurrent_streams_are_queued() {
+    runtime::block_on(async {
+        let fixture = serve(Server::builder().max_concurrent_streams(1));
+
+        let endpoint = fixture.endpoint.clone();
+        let first = runtime::spawn(async move { say(&endpoint, b"block").await });
+        fixture.entered.wait().await;
+
+        // the client holds the second stream back until the first one closes
+        let endpoint = fixture.endpoint.clone();
+        let second = runtime::spawn(async move { say(&endpoint, b"block").await });
+        Timer::after(Duration::from_millis(200)).await;
+        assert_eq!(fixture.calls.load(Ordering::SeqCst), 1);
+
+        fixture.release.wait().await;
+        assert_eq!(first.await.unwrap().data, b"block");
+
+        fixture.entered.wait().await;
+        assert_eq!(fixture.calls.load(Ordering::SeqCst), 2);
+        fixture.release.wait().await;
+        assert_eq!(second.await.unwrap().data, b"block");
+    });
+}
@@ -0,0 +1,257 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::DynamicCodec;
+use lucat::common::{Request, Response, Status};
+use lucat::dynamic::{DescriptorPool, DynamicMessage, Value};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet, OneofDescriptorProto,
+};
+
+pub mod echo {
+    lucat::include_proto!("echo");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = lucat::include_file_descriptor_set!("echo_descriptor");
+}
+
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name.map(|name| name + 1),
+        }))
+    }
+}
+
+#[test]
+fn unary_with_dynamic_codec() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(MyEcho::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let pool = DescriptorPool::decode(echo::FILE_DESCRIPTOR_SET).unwrap();
+        let method = pool
+            .get_service_by_name("echo.Echo")
+            .and_then(|service| service.get_method_by_name("SayEcho"))
+            .unwrap();
+        let codec = DynamicCodec::for_method(&method).unwrap();
+
+        let input = method.input().unwrap();
+        let mut request = DynamicMessage::new(input.clone());
+        let data = input.get_field_by_name("data").unwrap();
+        let name = input.get_field_by_name("name").unwrap();
+        request.set_field(&data, Value::Bytes(Bytes::from_static(&[1, 2, 3]))).unwrap();
+        request.set_field(&name, Value::U32(41)).unwrap();
+
+        let path: PathAndQuery = method.path().parse().unwrap();
+        let mut rpc = Rpc::new(endpoint);
+        let response = rpc.unary(Request::new(request), path, codec).await.unwrap();
+        let response = response.into_inner();
+
+        let output = method.output().unwrap();
+        assert_eq!(response.descriptor(), &output);
+        assert_eq!(
+            response.get_field(&output.get_field_by_name("data").unwrap()),
+            Some(&Value::Bytes(Bytes::from_static(&[1, 2, 3])))
+        );
+        assert_eq!(
+            response.get_field(&output.get_field_by_name("name").unwrap()),
+            Some(&Value::U32(42))
+        );
+        assert_eq!(response.get_field(&output.get_field_by_name("tag").unwrap()), None);
+    });
+}
+
+// message Node {
+//   Node child = 1; repeated int32 values = 2; string name = 3; int32 count = 4;
+//   fixed32 checksum = 5; oneof choice { string label = 6; int32 weight = 7; }
+// }
+fn node() -> lucat::dynamic::MessageDescriptor {
+    let field = |name: &str, number: i32, label: Label, ty: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(label as i32),
+        r#type: Some(ty as i32),
+        type_name: if ty == Type::Message { Some(".test.Node".to_string()) } else { None },
+        ..Default::default()
+    };
+    let choice = |name: &str, number: i32, ty: Type| FieldDescriptorProto {
+        oneof_index: Some(0),
+        ..field(name, number, Label::Optional, ty)
+    };
+    let set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("node.proto".to_string()),
+            package: Some("test".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Node".to_string()),
+                field: vec![
+                    field("child", 1, Label::Optional, Type::Message),
+                    field("values", 2, Label::Repeated, Type::Int32),
+                    field("name", 3, Label::Optional, Type::String),
+                    field("count", 4, Label::Optional, Type::Int32),
+                    field("checksum", 5, Label::Optional, Type::Fixed32),
+                    choice("label", 6, Type::String),
+                    choice("weight", 7, Type::Int32),
+                ],
+                oneof_decl: vec![OneofDescriptorProto {
+                    name: Some("choice".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        }],
+    };
+    let pool = DescriptorPool::decode(&set.encode_to_vec()).unwrap();
+    pool.get_message_by_name("test.Node").unwrap()
+}
+
+// `depth` nodes, each the child of the one before
+fn nested(depth: usize) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for _ in 0..depth {
+        let mut outer = vec![0x0a];
+        prost::encoding::encode_varint(buf.len() as u64, &mut outer);
+        outer.extend_from_slice(&buf);
+        buf = outer;
+    }
+    buf
+}
+
+#[test]
+fn deeply_nested_messages_are_rejected() {
+    let node = node();
+    assert!(DynamicMessage::decode(node.clone(), &nested(100)[..]).is_ok());
+    assert!(DynamicMessage::decode(node.clone(), &nested(101)[..]).is_err());
+    assert!(DynamicMessage::decode(node, &nested(5000)[..]).is_err());
+}
+
+#[test]
+fn repeated_embedded_messages_merge_recursively() {
+    let node = node();
+    let child = node.get_field_by_name("child").unwrap();
+    let values = node.get_field_by_name("values").unwrap();
+    let name = node.get_field_by_name("name").unwrap();
+    let count = node.get_field_by_name("count").unwrap();
+
+    let message = |fields: Vec<(&lucat::dynamic::FieldDescriptor, Value)>| {
+        let mut message = DynamicMessage::new(node.clone());
+        for (field, value) in fields {
+            message.set_field(field, value).unwrap();
+        }
+        message
+    };
+
+    let first = message(vec![(
+        &child,
+        Value::Message(message(vec![
+            (&name, Value::String("a".to_string())),
+            (&values, Value::List(vec![Value::I32(1)])),
+            (&child, Value::Message(message(vec![(&count, Value::I32(1))]))),
+        ])),
+    )]);
+    let second = message(vec![(
+        &child,
+        Value::Message(message(vec![
+            (&values, Value::List(vec![Value::I32(2)])),
+            (&child, Value::Message(message(vec![(&name, Value::String("b".to_string()))]))),
+        ])),
+    )]);
+
+    let mut encoded = first.encode_to_bytes().to_vec();
+    encoded.extend_from_slice(&second.encode_to_bytes());
+    let merged = DynamicMessage::decode(node.clone(), &encoded[..]).unwrap();
+
+    let expected = message(vec![(
+        &child,
+        Value::Message(message(vec![
+            (&name, Value::String("a".to_string())),
+            (&values, Value::List(vec![Value::I32(1), Value::I32(2)])),
+            (
+                &child,
+                Value::Message(message(vec![
+                    (&count, Value::I32(1)),
+                    (&name, Value::String("b".to_string())),
+                ])),
+            ),
+        ])),
+    )]);
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn values_must_match_the_field_type() {
+    let node = node();
+    let child = node.get_field_by_name("child").unwrap();
+    let values = node.get_field_by_name("values").unwrap();
+    let count = node.get_field_by_name("count").unwrap();
+    let checksum = node.get_field_by_name("checksum").unwrap();
+
+    let mut message = DynamicMessage::new(node.clone());
+    assert!(message.set_field(&count, Value::F64(1.5)).is_err());
+    assert!(message.set_field(&checksum, Value::I32(7)).is_err());
+    assert!(message.set_field(&count, Value::List(vec![Value::I32(1)])).is_err());
+    assert!(message.set_field(&values, Value::I32(1)).is_err());
+    assert!(message.set_field(&values, Value::List(vec![Value::I32(1), Value::U32(2)])).is_err());
+    assert!(message.set_field(&child, Value::Message(DynamicMessage::new(echo_request()))).is_err());
+    assert_eq!(message.fields().count(), 0);
+
+    message.set_field(&count, Value::I32(-1)).unwrap();
+    message.set_field(&checksum, Value::U32(7)).unwrap();
+    message.set_field(&values, Value::List(vec![Value::I32(1), Value::I32(2)])).unwrap();
+    message.set_field(&child, Value::Message(DynamicMessage::new(node.clone()))).unwrap();
+    let decoded = DynamicMessage::decode(node, message.encode_to_bytes()).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn setting_a_oneof_member_clears_the_others() {
+    let node = node();
+    let label = node.get_field_by_name("label").unwrap();
+    let weight = node.get_field_by_name("weight").unwrap();
+    let count = node.get_field_by_name("count").unwrap();
+
+    let mut message = DynamicMessage::new(node.clone());
+    message.set_field(&count, Value::I32(3)).unwrap();
+    message.set_field(&label, Value::String("heavy".to_string())).unwrap();
+    message.set_field(&weight, Value::I32(9)).unwrap();
+    assert_eq!(message.get_field(&label), None);
+    assert_eq!(message.get_field(&weight), Some(&Value::I32(9)));
+    assert_eq!(message.get_field(&count), Some(&Value::I32(3)));
+
+    // on the wire the last member wins too
+    let mut other = DynamicMessage::new(node.clone());
+    other.set_field(&label, Value::String("light".to_string())).unwrap();
+    let mut encoded = message.encode_to_bytes().to_vec();
+    encoded.extend_from_slice(&other.encode_to_bytes());
+    let decoded = DynamicMessage::decode(node, &encoded[..]).unwrap();
+    assert_eq!(decoded.get_field(&weight), None);
+    assert_eq!(decoded.get_field(&label), Some(&Value::String("light".to_string())));
+}
+
+fn echo_request() -> lucat::dynamic::MessageDescriptor {
+    let pool = DescriptorPool::decode(echo::FILE_DESCRIPTOR_SET).unwrap();
+    pool.get_message_by_name("echo.EchoRequest").unwrap()
+}
@@ -0,0 +1,98 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::{self, StreamExt};
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::{BytesCodec, Streaming};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+#[test]
+fn unknown_services_reach_the_fallback() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        // answers every frame with the method path followed by the frame
+        let fallback = |path: PathAndQuery, request: Request<Streaming<Bytes>>| async move {
+            let frames = request.into_inner().map(move |frame| {
+                let mut reply = path.as_str().as_bytes().to_vec();
+                reply.extend_from_slice(&frame?);
+                Ok(Bytes::from(reply))
+            });
+            Ok(Response::new(frames))
+        };
+
+        let mut server = Server::builder();
+        let router = server
+            .register(EchoServer::new(MyEcho::default()))
+            .fallback(fallback);
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut rpc = Rpc::new(endpoint);
+        let frames = stream::iter(vec![Bytes::from_static(b":a"), Bytes::from_static(b":b")]);
+        let path = PathAndQuery::from_static("/other.Service/Call");
+        let response = rpc.streaming(Request::new(frames), path, BytesCodec).await.unwrap();
+
+        let mut replies = response.into_inner();
+        assert_eq!(replies.message().await.unwrap().unwrap(), "/other.Service/Call:a");
+        assert_eq!(replies.message().await.unwrap().unwrap(), "/other.Service/Call:b");
+        assert_eq!(replies.message().await.unwrap(), None);
+    });
+}
+
+#[test]
+fn registered_services_bypass_the_fallback() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let fallback = |_: PathAndQuery, _: Request<Streaming<Bytes>>| async move {
+            Err::<Response<Streaming<Bytes>>, _>(Status::internal("fallback called"))
+        };
+
+        let mut server = Server::builder();
+        let router = server
+            .register(EchoServer::new(MyEcho::default()))
+            .fallback(fallback);
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        let request = EchoRequest {
+            data: vec![1, 2, 3],
+            tag: vec![],
+            name: Some(7),
+        };
+        let response = client.say_echo(Request::new(request)).await.unwrap().into_inner();
+        assert_eq!(response.data, vec![1, 2, 3]);
+        assert_eq!(response.name, Some(7));
+    });
+}
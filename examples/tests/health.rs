@@ -0,0 +1,91 @@
+use cynthia::runtime::{self, stream::StreamExt};
+use lucat::common::{Request, Response, Status};
+use lucat::health::pb::health_client::HealthClient;
+use lucat::health::pb::HealthCheckRequest;
+use lucat::health::{self, ServingStatus};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+fn check_request(service: &str) -> Request<HealthCheckRequest> {
+    Request::new(HealthCheckRequest {
+        service: service.to_string(),
+    })
+}
+
+#[test]
+fn health_check_and_watch() {
+    runtime::block_on(async {
+        let (health_endpoint, health_incoming) = Endpoint::in_memory();
+        let (echo_endpoint, echo_incoming) = Endpoint::in_memory();
+        let (reporter, health_service) = health::health_reporter();
+
+        let mut server = Server::builder();
+        let router = server
+            .register(EchoServer::new(MyEcho::default()))
+            .add_service(health_service);
+        runtime::spawn(async move {
+            let _ = router
+                .serve_with_incoming(health_incoming.or(echo_incoming))
+                .await;
+        })
+        .detach();
+
+        let mut client = HealthClient::new(health_endpoint);
+
+        let response = client.check(check_request("")).await.unwrap();
+        assert_eq!(response.into_inner().status, ServingStatus::Serving as i32);
+
+        let status = client.check(check_request("echo.Echo")).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        reporter.set_serving::<EchoServer<MyEcho>>();
+        let response = client.check(check_request("echo.Echo")).await.unwrap();
+        assert_eq!(response.into_inner().status, ServingStatus::Serving as i32);
+
+        let mut updates = client
+            .watch(check_request("echo.Echo"))
+            .await
+            .unwrap()
+            .into_inner();
+        let update = updates.message().await.unwrap().unwrap();
+        assert_eq!(update.status, ServingStatus::Serving as i32);
+
+        reporter.set_not_serving::<EchoServer<MyEcho>>();
+        let update = updates.message().await.unwrap().unwrap();
+        assert_eq!(update.status, ServingStatus::NotServing as i32);
+
+        // the echo service is still reachable next to the health service
+        let mut echo = EchoClient::new(echo_endpoint);
+        let request = EchoRequest {
+            data: vec![1, 2, 3],
+            tag: vec![4],
+            name: Some(5),
+        };
+        let response = echo.say_echo(Request::new(request.clone())).await.unwrap();
+        assert_eq!(response.into_inner().data, request.data);
+    });
+}
@@ -0,0 +1,90 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::{self, StreamExt};
+use http::uri::PathAndQuery;
+use http::{Response as HttpResponse, StatusCode};
+use lucat::client::Rpc;
+use lucat::codec::BytesCodec;
+use lucat::common::{Request, Status};
+use lucat::transport::Endpoint;
+use lucat::Code;
+use nephele::proto::h2::server;
+
+// Answers every call the way a plain HTTP intermediary would, with `status`,
+// `content_type` and a body that is not a gRPC message.
+fn intermediary(status: StatusCode, content_type: &'static str) -> Endpoint {
+    let (endpoint, mut incoming) = Endpoint::in_memory();
+    runtime::spawn(async move {
+        while let Some(Ok(socket)) = incoming.next().await {
+            runtime::spawn(async move {
+                let mut connection = server::handshake(socket).await.unwrap();
+                while let Some(Ok((_, mut respond))) = connection.accept().await {
+                    let response = HttpResponse::builder()
+                        .status(status)
+                        .header("content-type", content_type)
+                        .body(())
+                        .unwrap();
+                    let mut body = respond.send_response(response, false).unwrap();
+                    let _ = body.send_data(Bytes::from_static(b"<html>oops</html>"), true);
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    endpoint
+}
+
+async fn call(endpoint: Endpoint) -> Status {
+    let mut rpc = Rpc::new(endpoint);
+    let frames = stream::iter(vec![Bytes::from_static(b"hello")]);
+    let path = PathAndQuery::from_static("/echo.Echo/SayEcho");
+    match rpc.streaming(Request::new(frames), path, BytesCodec).await {
+        Ok(response) => response.into_inner().message().await.unwrap_err(),
+        Err(status) => status,
+    }
+}
+
+#[test]
+fn http_errors_map_to_grpc_codes() {
+    runtime::block_on(async {
+        let cases = [
+            (StatusCode::BAD_GATEWAY, Code::Unavailable),
+            (StatusCode::SERVICE_UNAVAILABLE, Code::Unavailable),
+            (StatusCode::TOO_MANY_REQUESTS, Code::Unavailable),
+            (StatusCode::NOT_FOUND, Code::Unimplemented),
+            (StatusCode::UNAUTHORIZED, Code::Unauthenticated),
+            (StatusCode::INTERNAL_SERVER_ERROR, Code::Unknown),
+        ];
+        for (http_status, code) in cases.iter() {
+            let status = call(intermediary(*http_status, "text/html")).await;
+            assert_eq!(status.code(), *code, "for HTTP {}", http_status);
+        }
+    });
+}
+
+#[test]
+fn non_grpc_content_type_is_rejected() {
+    runtime::block_on(async {
+        let status = call(intermediary(StatusCode::OK, "text/html")).await;
+        assert_eq!(status.code(), Code::Unknown);
+        assert!(status.message().contains("text/html"));
+    });
+}
+
+#[test]
+fn content_types_only_resembling_grpc_are_rejected() {
+    runtime::block_on(async {
+        for content_type in [
+            "application/grpcx",
+            "application/grpc-web",
+            "application/grpc+",
+        ]
+        .iter()
+        {
+            let status = call(intermediary(StatusCode::OK, content_type)).await;
+            assert_eq!(status.code(), Code::Unknown, "for {}", content_type);
+            assert!(status.message().contains(content_type));
+        }
+    });
+}
@@ -0,0 +1,56 @@
+use cynthia::runtime;
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+#[test]
+fn unary_over_in_memory_transport() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(MyEcho::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        for i in 0..3 {
+            let request = EchoRequest {
+                data: vec![i, 2, 5],
+                tag: vec![1],
+                name: Some(150),
+            };
+
+            let response = client.say_echo(Request::new(request.clone())).await.unwrap();
+            let response = response.into_inner();
+            assert_eq!(response.data, request.data);
+            assert_eq!(response.tag, request.tag);
+            assert_eq!(response.name, request.name);
+        }
+    });
+}
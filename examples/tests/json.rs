@@ -0,0 +1,254 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::{BytesDecoder, BytesEncoder, Codec, ProstCodec};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+use serde_json::Value as Json;
+
+pub mod greeter {
+    lucat::include_proto!("greeter");
+}
+
+pub mod mapping {
+    lucat::include_proto!("json");
+}
+
+use greeter::greeter_client::GreeterClient;
+use greeter::greeter_server::{Greeter, GreeterServer};
+use greeter::{HelloReply, HelloRequest};
+use mapping::mapping_client::MappingClient;
+use mapping::mapping_server::{Mapping, MappingServer};
+use mapping::{sample, Sample, Size};
+
+// `sample()` in the canonical JSON mapping
+const SAMPLE_JSON: &str = r#"{
+    "id": "9007199254740993",
+    "payload": "AAH+/w==",
+    "size": "SIZE_LARGE",
+    "number": 0,
+    "createdAt": "2020-09-13T12:26:40.500Z"
+}"#;
+
+#[derive(Default, Clone)]
+struct MyGreeter {}
+
+#[lucat::async_trait]
+impl Greeter for MyGreeter {
+    async fn say_hello(&self, request: Request<HelloRequest>) -> Result<Response<HelloReply>, Status> {
+        let r = request.into_inner();
+        if r.name.is_empty() {
+            return Err(Status::invalid_argument("name is empty"));
+        }
+        Ok(Response::new(HelloReply {
+            message: format!("hello {}", r.name),
+            greeted_names: vec![r.name; r.times as usize],
+        }))
+    }
+
+    async fn say_hello_proto(&self, request: Request<HelloRequest>) -> Result<Response<HelloReply>, Status> {
+        self.say_hello(request).await
+    }
+}
+
+#[derive(Default, Clone)]
+struct MyMapping {}
+
+#[lucat::async_trait]
+impl Mapping for MyMapping {
+    async fn echo(&self, request: Request<Sample>) -> Result<Response<Sample>, Status> {
+        Ok(Response::new(request.into_inner()))
+    }
+}
+
+// Sends and receives message payloads untouched, with the content-type `T`
+// names, to look at what goes over the wire.
+struct RawCodec<T>(std::marker::PhantomData<T>);
+
+impl<T> Default for RawCodec<T> {
+    fn default() -> Self {
+        RawCodec(std::marker::PhantomData)
+    }
+}
+
+struct GrpcJson;
+struct GrpcThrift;
+
+trait ContentType {
+    const CONTENT_TYPE: &'static str;
+}
+
+impl ContentType for GrpcJson {
+    const CONTENT_TYPE: &'static str = "application/grpc+json";
+}
+
+impl ContentType for GrpcThrift {
+    const CONTENT_TYPE: &'static str = "application/grpc+thrift";
+}
+
+impl<T: ContentType> Codec for RawCodec<T> {
+    const CONTENT_TYPE: &'static str = T::CONTENT_TYPE;
+
+    type Encode = Bytes;
+    type Decode = Bytes;
+
+    type Encoder = BytesEncoder;
+    type Decoder = BytesDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesDecoder
+    }
+}
+
+fn sample() -> Sample {
+    Sample {
+        // more digits than a JavaScript number keeps
+        id: 9_007_199_254_740_993,
+        payload: vec![0, 1, 254, 255],
+        size: Size::Large as i32,
+        // a oneof member is written even when it holds the default value
+        choice: Some(sample::Choice::Number(0)),
+        created_at: Some(prost_types::Timestamp {
+            seconds: 1_600_000_000,
+            nanos: 500_000_000,
+        }),
+    }
+}
+
+fn serve_mapping() -> Endpoint {
+    let (endpoint, incoming) = Endpoint::in_memory();
+
+    let mut server = Server::builder();
+    let router = server.register(MappingServer::new(MyMapping::default()));
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    endpoint
+}
+
+fn echo_path() -> PathAndQuery {
+    PathAndQuery::from_static("/json.Mapping/Echo")
+}
+
+#[test]
+fn unary_with_json_and_overridden_codecs() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(GreeterServer::new(MyGreeter::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = GreeterClient::new(endpoint);
+        let request = HelloRequest {
+            name: "lucat".to_string(),
+            times: 2,
+        };
+        let reply = client.say_hello(Request::new(request)).await.unwrap().into_inner();
+        assert_eq!(reply.message, "hello lucat");
+        assert_eq!(reply.greeted_names, vec!["lucat", "lucat"]);
+
+        let request = HelloRequest::default();
+        assert!(client.say_hello(Request::new(request)).await.is_err());
+
+        let request = HelloRequest {
+            name: "proto".to_string(),
+            times: 1,
+        };
+        let reply = client.say_hello_proto(Request::new(request)).await.unwrap().into_inner();
+        assert_eq!(reply.message, "hello proto");
+        assert_eq!(reply.greeted_names, vec!["proto"]);
+    });
+}
+
+#[test]
+fn canonical_json_round_trips() {
+    runtime::block_on(async {
+        let mut client = MappingClient::new(serve_mapping());
+
+        let reply = client.echo(Request::new(sample())).await.unwrap().into_inner();
+        assert_eq!(reply, sample());
+
+        let request = Sample {
+            id: -1,
+            choice: Some(sample::Choice::Text("lucat".to_string())),
+            created_at: Some(prost_types::Timestamp {
+                seconds: -62_135_596_800,
+                nanos: 1,
+            }),
+            ..Sample::default()
+        };
+        let reply = client.echo(Request::new(request.clone())).await.unwrap().into_inner();
+        assert_eq!(reply, request);
+    });
+}
+
+#[test]
+fn messages_are_canonical_json_on_the_wire() {
+    runtime::block_on(async {
+        let mut rpc = Rpc::new(serve_mapping());
+
+        let request = Request::new(Bytes::from(SAMPLE_JSON));
+        let reply = rpc
+            .unary(request, echo_path(), RawCodec::<GrpcJson>::default())
+            .await
+            .unwrap()
+            .into_inner();
+        let reply: Json = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(reply, serde_json::from_str::<Json>(SAMPLE_JSON).unwrap());
+
+        // protobuf names and numeric enums are accepted too
+        let request = Request::new(Bytes::from(r#"{"created_at":"2020-09-13T14:26:40.5+02:00","size":1}"#));
+        let reply = rpc
+            .unary(request, echo_path(), RawCodec::<GrpcJson>::default())
+            .await
+            .unwrap()
+            .into_inner();
+        let reply: Json = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(
+            reply,
+            serde_json::json!({"size": "SIZE_SMALL", "createdAt": "2020-09-13T12:26:40.500Z"})
+        );
+
+        let request = Request::new(Bytes::from(r#"{"text":"a","number":1}"#));
+        let status = rpc
+            .unary(request, echo_path(), RawCodec::<GrpcJson>::default())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+        assert!(status.message().contains("oneof choice"), "{}", status.message());
+    });
+}
+
+#[test]
+fn servers_negotiate_the_format_from_the_content_type() {
+    runtime::block_on(async {
+        let mut rpc = Rpc::new(serve_mapping());
+
+        let reply = rpc
+            .unary(Request::new(sample()), echo_path(), ProstCodec::<Sample, Sample>::default())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(reply, sample());
+
+        let request = Request::new(Bytes::from("{}"));
+        let status = rpc
+            .unary(request, echo_path(), RawCodec::<GrpcThrift>::default())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+    });
+}
@@ -0,0 +1,285 @@
+use cynthia::io::Timer;
+use cynthia::runtime::stream::{self, StreamExt};
+use cynthia::runtime::swap::{AsyncReadExt, AsyncWriteExt};
+use cynthia::runtime::{self, future};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::{duplex, DuplexStream};
+use nephele::proto::h2::{client, Reason};
+use std::io;
+use std::time::{Duration, Instant};
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_PING: u8 = 0x6;
+const FRAME_GOAWAY: u8 = 0x7;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+
+// HPACK for a POST to /echo.Echo/SayEcho, built from the static table and
+// literals so that no encoder state is needed
+const CALL_HEADERS: &[u8] = b"\x83\x86\x04\x12/echo.Echo/SayEcho\
+    \x0f\x10\x10application/grpc\x00\x02te\x08trailers";
+
+#[derive(Default)]
+struct MyEcho;
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+fn serve(server: Server) -> DuplexStream {
+    let (client_io, server_io) = duplex(64 * 1024);
+    let mut server = server;
+    let router = server.register(EchoServer::new(MyEcho));
+    runtime::spawn(async move {
+        // the listener stays open, or the server would close the connection
+        let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+    client_io
+}
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    payload: Vec<u8>,
+}
+
+async fn write_frame(io: &mut DuplexStream, kind: u8, flags: u8, payload: &[u8]) {
+    write_stream_frame(io, 0, kind, flags, payload).await;
+}
+
+async fn write_stream_frame(
+    io: &mut DuplexStream,
+    stream: u32,
+    kind: u8,
+    flags: u8,
+    payload: &[u8],
+) {
+    let len = (payload.len() as u32).to_be_bytes();
+    let id = stream.to_be_bytes();
+    let header = [
+        len[1], len[2], len[3], kind, flags, id[0], id[1], id[2], id[3],
+    ];
+    io.write_all(&header).await.unwrap();
+    io.write_all(payload).await.unwrap();
+}
+
+// `None` once the server closed the connection
+async fn read_frame(io: &mut DuplexStream) -> Option<Frame> {
+    let mut header = [0; 9];
+    io.read_exact(&mut header).await.ok()?;
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let mut payload = vec![0; len];
+    io.read_exact(&mut payload).await.ok()?;
+    Some(Frame {
+        kind: header[3],
+        flags: header[4],
+        payload,
+    })
+}
+
+// Speaks just enough HTTP/2 by hand to leave out what h2 does on its own,
+// like answering pings.
+async fn handshake(io: &mut DuplexStream) {
+    io.write_all(PREFACE).await.unwrap();
+    write_frame(io, FRAME_SETTINGS, 0, &[]).await;
+    loop {
+        let frame = read_frame(io).await.unwrap();
+        if frame.kind == FRAME_SETTINGS && frame.flags & FLAG_ACK == 0 {
+            write_frame(io, FRAME_SETTINGS, FLAG_ACK, &[]).await;
+            return;
+        }
+    }
+}
+
+async fn within<T>(millis: u64, f: impl std::future::Future<Output = T>) -> T {
+    let timeout = async {
+        Timer::after(Duration::from_millis(millis)).await;
+        panic!("timed out after {}ms", millis);
+    };
+    future::or(f, timeout).await
+}
+
+// the reason of the GOAWAY the server answers with
+async fn goaway(io: &mut DuplexStream) -> Reason {
+    let goaway = within(1000, async {
+        loop {
+            let frame = read_frame(io).await.expect("closed without GOAWAY");
+            if frame.kind == FRAME_GOAWAY {
+                return frame;
+            }
+        }
+    })
+    .await;
+    let code = &goaway.payload[4..8];
+    Reason::from(u32::from_be_bytes([code[0], code[1], code[2], code[3]]))
+}
+
+#[test]
+fn peers_that_never_ack_pings_are_disconnected() {
+    runtime::block_on(async {
+        let server = Server::builder()
+            .keepalive_interval(Duration::from_millis(50))
+            .keepalive_timeout(Duration::from_millis(100))
+            .keepalive_while_idle(true);
+        // the first ping goes out an interval after the connection starts and
+        // the close a timeout after that, however late the frames are read
+        let started = Instant::now();
+        let mut io = serve(server);
+        handshake(&mut io).await;
+
+        let mut pinged = false;
+        within(2000, async {
+            while let Some(frame) = read_frame(&mut io).await {
+                if frame.kind == FRAME_PING && frame.flags & FLAG_ACK == 0 {
+                    pinged = true;
+                }
+            }
+        })
+        .await;
+
+        assert!(pinged, "server never pinged");
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    });
+}
+
+#[test]
+fn peers_that_ack_pings_stay_connected() {
+    runtime::block_on(async {
+        let server = Server::builder()
+            .keepalive_interval(Duration::from_millis(50))
+            .keepalive_timeout(Duration::from_millis(100))
+            .keepalive_while_idle(true);
+        let io = serve(server);
+
+        let (mut client, connection) = client::handshake(io).await.unwrap();
+        runtime::spawn(async move {
+            let _ = connection.await;
+        })
+        .detach();
+
+        // several rounds of keepalive pings, all answered by h2
+        Timer::after(Duration::from_millis(500)).await;
+        let request = http::Request::get("http://localhost/").body(()).unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        assert!(within(1000, response).await.is_ok());
+    });
+}
+
+#[test]
+fn peers_pinging_too_often_get_goaway_enhance_your_calm() {
+    runtime::block_on(async {
+        let server = Server::builder().min_ping_interval(Duration::from_secs(10));
+        let mut io = serve(server);
+        handshake(&mut io).await;
+
+        for _ in 0..5 {
+            write_frame(&mut io, FRAME_PING, 0, &[0; 8]).await;
+        }
+        assert_eq!(goaway(&mut io).await, Reason::ENHANCE_YOUR_CALM);
+    });
+}
+
+#[test]
+fn ping_policy_is_on_by_default() {
+    runtime::block_on(async {
+        let mut io = serve(Server::builder());
+        handshake(&mut io).await;
+
+        for _ in 0..5 {
+            write_frame(&mut io, FRAME_PING, 0, &[0; 8]).await;
+        }
+        assert_eq!(goaway(&mut io).await, Reason::ENHANCE_YOUR_CALM);
+    });
+}
+
+#[test]
+fn client_frames_do_not_forgive_pings() {
+    runtime::block_on(async {
+        let server = Server::builder().min_ping_interval(Duration::from_secs(10));
+        let mut io = serve(server);
+        handshake(&mut io).await;
+
+        // the call never completes, so the server has nothing to send back
+        write_stream_frame(&mut io, 1, FRAME_HEADERS, FLAG_END_HEADERS, CALL_HEADERS).await;
+        for _ in 0..5 {
+            write_frame(&mut io, FRAME_PING, 0, &[0; 8]).await;
+            write_stream_frame(&mut io, 1, FRAME_DATA, 0, &[]).await;
+        }
+        assert_eq!(goaway(&mut io).await, Reason::ENHANCE_YOUR_CALM);
+    });
+}
+
+#[test]
+fn server_responses_forgive_pings() {
+    runtime::block_on(async {
+        let server = Server::builder().min_ping_interval(Duration::from_secs(10));
+        let mut io = serve(server);
+        handshake(&mut io).await;
+
+        // an empty EchoRequest in a gRPC frame
+        let message = [0; 5];
+        for stream in (1..10).step_by(2) {
+            write_frame(&mut io, FRAME_PING, 0, &[0; 8]).await;
+            write_stream_frame(
+                &mut io,
+                stream,
+                FRAME_HEADERS,
+                FLAG_END_HEADERS,
+                CALL_HEADERS,
+            )
+            .await;
+            write_stream_frame(&mut io, stream, FRAME_DATA, FLAG_END_STREAM, &message).await;
+            within(1000, async {
+                loop {
+                    let frame = read_frame(&mut io).await.expect("closed during a call");
+                    assert_ne!(frame.kind, FRAME_GOAWAY);
+                    if frame.kind == FRAME_HEADERS && frame.flags & FLAG_END_STREAM != 0 {
+                        return;
+                    }
+                }
+            })
+            .await;
+        }
+
+        // the connection is still up and answers the next ping
+        write_frame(&mut io, FRAME_PING, 0, &[0; 8]).await;
+        within(1000, async {
+            loop {
+                let frame = read_frame(&mut io)
+                    .await
+                    .expect("closed without a ping ack");
+                assert_ne!(frame.kind, FRAME_GOAWAY);
+                if frame.kind == FRAME_PING && frame.flags & FLAG_ACK != 0 {
+                    return;
+                }
+            }
+        })
+        .await;
+    });
+}
@@ -0,0 +1,201 @@
+use bytes::{Buf, Bytes};
+use cynthia::io::Timer;
+use cynthia::platform::lock::Barrier;
+use cynthia::runtime::stream::{self, StreamExt};
+use cynthia::runtime::{self, future, task::Task};
+use http::Request as HttpRequest;
+use lucat::common::{Request, Response, Status};
+use lucat::transport::duplex;
+use lucat::transport::server::Server;
+use nephele::proto::h2::client::{self, SendRequest};
+use nephele::proto::h2::Error;
+use prost::Message;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+// Calls with data "block" meet the test on `entered` and wait until it lets
+// them go through `release`, anything else is echoed at once.
+#[derive(Clone)]
+struct MyEcho {
+    entered: Arc<Barrier>,
+    release: Arc<Barrier>,
+}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        if r.data == b"block" {
+            self.entered.wait().await;
+            self.release.wait().await;
+        }
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+struct Connection {
+    client: SendRequest<Bytes>,
+    closed: Task<Result<(), Error>>,
+    entered: Arc<Barrier>,
+    release: Arc<Barrier>,
+}
+
+// Serves one in-memory connection and talks to it straight over HTTP/2, so
+// that the GOAWAY and the close of the connection can be seen.
+async fn connect(server: Server) -> Connection {
+    let (client_io, server_io) = duplex(64 * 1024);
+    let echo = MyEcho {
+        entered: Arc::new(Barrier::new(2)),
+        release: Arc::new(Barrier::new(2)),
+    };
+
+    let mut server = server;
+    let router = server.register(EchoServer::new(echo.clone()));
+    runtime::spawn(async move {
+        // the listener stays open, or the server would close the connection
+        let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    let (client, connection) = client::handshake(client_io).await.unwrap();
+    Connection {
+        client,
+        closed: runtime::spawn(connection),
+        entered: echo.entered,
+        release: echo.release,
+    }
+}
+
+fn start(client: &mut SendRequest<Bytes>, data: &[u8]) -> Result<client::ResponseFuture, Error> {
+    let request = HttpRequest::post("http://localhost/echo.Echo/SayEcho")
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(())
+        .unwrap();
+    let (response, mut stream) = client.send_request(request, false)?;
+
+    let message = EchoRequest {
+        data: data.to_vec(),
+        ..EchoRequest::default()
+    };
+    let frame = lucat::codec::encode_frame(Bytes::from(message.encode_to_vec())).unwrap();
+    stream.send_data(frame, true)?;
+    Ok(response)
+}
+
+async fn finish(response: client::ResponseFuture) -> Result<EchoResponse, Error> {
+    let mut body = response.await?.into_body();
+    let mut frame = Vec::new();
+    while let Some(data) = body.data().await {
+        frame.extend_from_slice(&data?);
+    }
+    let trailers = body.trailers().await?.unwrap_or_default();
+    assert_eq!(trailers["grpc-status"], "0");
+
+    let mut frame = &frame[..];
+    frame.advance(5);
+    Ok(EchoResponse::decode(frame).unwrap())
+}
+
+async fn sleep(millis: u64) {
+    Timer::after(Duration::from_millis(millis)).await;
+}
+
+// waits for the server to close the connection, failing the test if it doesn't
+async fn closed(connection: Connection) {
+    let timeout = async {
+        sleep(1000).await;
+        panic!("connection still open");
+    };
+    future::or(connection.closed, timeout).await.unwrap();
+}
+
+#[test]
+fn connections_past_their_max_age_are_closed() {
+    runtime::block_on(async {
+        let server = Server::builder().max_connection_age(Duration::from_millis(50));
+        let mut connection = connect(server).await;
+
+        let reply = finish(start(&mut connection.client, b"young").unwrap()).await;
+        assert_eq!(reply.unwrap().data, b"young");
+
+        sleep(150).await;
+        assert!(start(&mut connection.client, b"old").is_err());
+        closed(connection).await;
+    });
+}
+
+#[test]
+fn in_flight_calls_finish_within_the_age_grace() {
+    runtime::block_on(async {
+        let server = Server::builder()
+            .max_connection_age(Duration::from_millis(50))
+            .max_connection_age_grace(Duration::from_secs(5));
+        let mut connection = connect(server).await;
+
+        let in_flight = start(&mut connection.client, b"block").unwrap();
+        connection.entered.wait().await;
+
+        // past its age the connection takes no new streams, but keeps the open one
+        sleep(150).await;
+        assert!(start(&mut connection.client, b"late").is_err());
+
+        connection.release.wait().await;
+        assert_eq!(finish(in_flight).await.unwrap().data, b"block");
+        closed(connection).await;
+    });
+}
+
+#[test]
+fn in_flight_calls_are_cut_off_after_the_age_grace() {
+    runtime::block_on(async {
+        let server = Server::builder()
+            .max_connection_age(Duration::from_millis(50))
+            .max_connection_age_grace(Duration::from_millis(50));
+        let mut connection = connect(server).await;
+
+        let in_flight = start(&mut connection.client, b"block").unwrap();
+        connection.entered.wait().await;
+
+        // the handler is never released
+        assert!(finish(in_flight).await.is_err());
+        closed(connection).await;
+    });
+}
+
+#[test]
+fn idle_connections_are_closed() {
+    runtime::block_on(async {
+        let server = Server::builder().max_connection_idle(Duration::from_millis(100));
+        let mut connection = connect(server).await;
+
+        // a call that outlasts the idle timeout keeps the connection open
+        let in_flight = start(&mut connection.client, b"block").unwrap();
+        connection.entered.wait().await;
+        sleep(200).await;
+        let reply = finish(start(&mut connection.client, b"busy").unwrap()).await;
+        assert_eq!(reply.unwrap().data, b"busy");
+        connection.release.wait().await;
+        assert_eq!(finish(in_flight).await.unwrap().data, b"block");
+
+        sleep(300).await;
+        assert!(start(&mut connection.client, b"idle").is_err());
+        closed(connection).await;
+    });
+}
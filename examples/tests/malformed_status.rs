@@ -0,0 +1,160 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::StreamExt;
+use http::header::{HeaderMap, HeaderValue};
+use http::Response as HttpResponse;
+use lucat::common::Request;
+use lucat::metadata::{AsciiMetadataKey, BinaryMetadataKey};
+use lucat::transport::Endpoint;
+use lucat::{Code, Status};
+use nephele::proto::h2::server;
+use prost::Message;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::{EchoRequest, EchoResponse};
+
+fn trailers(pairs: &[(&'static str, &[u8])]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.insert(*name, HeaderValue::from_bytes(value).unwrap());
+    }
+    headers
+}
+
+#[test]
+fn malformed_trailers_give_a_status() {
+    let status = Status::from_header_map(&trailers(&[
+        ("grpc-status", b"5"),
+        ("grpc-status-details-bin", b"not base64!"),
+        ("x-request-id", b"7"),
+    ]))
+    .unwrap();
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.metadata().get("x-request-id").unwrap(), "7");
+
+    let status = Status::from_header_map(&trailers(&[
+        ("grpc-status", b"5"),
+        ("grpc-message", b"%ff%fe"),
+    ]))
+    .unwrap();
+    assert_eq!(status.code(), Code::Unknown);
+
+    let status = Status::from_header_map(&trailers(&[("grpc-status", b"99")])).unwrap();
+    assert_eq!(status.code(), Code::Unknown);
+
+    assert!(Status::from_header_map(&trailers(&[("grpc-message", b"no status")])).is_none());
+}
+
+#[test]
+fn metadata_keys_of_the_wrong_kind_are_rejected() {
+    assert!(AsciiMetadataKey::from_bytes(b"trace-bin").is_err());
+    assert!(BinaryMetadataKey::from_bytes(b"trace").is_err());
+    assert!(BinaryMetadataKey::from_bytes(b"trace-bin").is_ok());
+}
+
+// A cheap sweep over generated trailers; the fuzz targets in lucat/fuzz go
+// much further.
+#[test]
+fn generated_trailers_never_panic() {
+    let alphabet = b"0123456789%=+/-_ abcXYZ\t\x7f\x80\xff";
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    for _ in 0..10_000 {
+        let mut value = || -> Vec<u8> {
+            let len = (next() % 12) as usize;
+            (0..len)
+                .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                .collect()
+        };
+
+        let mut headers = HeaderMap::new();
+        for name in ["grpc-status", "grpc-message", "grpc-status-details-bin"].iter() {
+            if let Ok(value) = HeaderValue::from_bytes(&value()) {
+                headers.insert(*name, value);
+            }
+        }
+
+        if let Some(status) = Status::from_header_map(&headers) {
+            let _ = status.to_header_map();
+        }
+    }
+}
+
+// Answers every call with a message, then ends the stream with `trailers`,
+// or without any when there are none.
+fn ending_with(trailers: Option<HeaderMap>) -> Endpoint {
+    let (endpoint, mut incoming) = Endpoint::in_memory();
+    runtime::spawn(async move {
+        while let Some(Ok(socket)) = incoming.next().await {
+            let trailers = trailers.clone();
+            runtime::spawn(async move {
+                let mut connection = server::handshake(socket).await.unwrap();
+                while let Some(Ok((request, mut respond))) = connection.accept().await {
+                    let trailers = trailers.clone();
+                    runtime::spawn(async move {
+                        // read the whole request, or h2 resets the stream
+                        // once the response ends
+                        let mut request = request.into_body();
+                        while request.data().await.is_some() {}
+
+                        let response = HttpResponse::builder()
+                            .header("content-type", "application/grpc")
+                            .body(())
+                            .unwrap();
+                        let mut body = respond.send_response(response, false).unwrap();
+                        let reply = EchoResponse::default().encode_to_vec();
+                        let frame = lucat::codec::encode_frame(Bytes::from(reply)).unwrap();
+                        match trailers {
+                            Some(trailers) => {
+                                body.send_data(frame, false).unwrap();
+                                body.send_trailers(trailers).unwrap();
+                            }
+                            None => body.send_data(frame, true).unwrap(),
+                        }
+                    })
+                    .detach();
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    endpoint
+}
+
+#[test]
+fn responses_without_grpc_status_fail() {
+    runtime::block_on(async {
+        let cases = vec![
+            Some(trailers(&[("x-request-id", b"7")])),
+            Some(HeaderMap::new()),
+            None,
+        ];
+        for case in cases {
+            let mut client = EchoClient::new(ending_with(case.clone()));
+            let status = client
+                .say_echo(Request::new(EchoRequest::default()))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), Code::Internal, "for trailers {:?}", case);
+            assert_eq!(status.message(), "missing grpc-status in trailers");
+        }
+
+        let ok = trailers(&[("grpc-status", b"0")]);
+        let mut client = EchoClient::new(ending_with(Some(ok)));
+        assert!(client
+            .say_echo(Request::new(EchoRequest::default()))
+            .await
+            .is_ok());
+    });
+}
@@ -0,0 +1,200 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::{self, StreamExt};
+use http::{Request as HttpRequest, Response as HttpResponse};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::{duplex, Endpoint};
+use lucat::Code;
+use nephele::proto::h2::{client, server};
+use std::io;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+// A frame header that announces a message of almost 4 GiB.
+const OVERSIZED: &[u8] = b"\x00\xff\xff\xff\xf6\x00\x00\x00";
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+fn request(len: usize) -> Request<EchoRequest> {
+    Request::new(EchoRequest {
+        data: vec![7; len],
+        tag: vec![],
+        name: None,
+    })
+}
+
+fn serve(server: Server, echo: EchoServer<MyEcho>) -> Endpoint {
+    let (endpoint, incoming) = Endpoint::in_memory();
+    let mut server = server;
+    let router = server.register(echo);
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+    endpoint
+}
+
+#[test]
+fn oversized_frame_header_is_rejected_by_the_server() {
+    runtime::block_on(async {
+        let (client_io, server_io) = duplex(64 * 1024);
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(MyEcho::default()));
+        runtime::spawn(async move {
+            let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let (mut client, connection) = client::handshake(client_io).await.unwrap();
+        runtime::spawn(async move {
+            let _ = connection.await;
+        })
+        .detach();
+
+        let request = HttpRequest::post("http://localhost/echo.Echo/SayEcho")
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(())
+            .unwrap();
+        let (response, mut body) = client.send_request(request, false).unwrap();
+        body.send_data(Bytes::from_static(OVERSIZED), false)
+            .unwrap();
+
+        let response = response.await.unwrap();
+        assert_eq!(response.headers()["grpc-status"], "8");
+    });
+}
+
+#[test]
+fn oversized_frame_header_is_rejected_by_the_client() {
+    runtime::block_on(async {
+        let (endpoint, mut incoming) = Endpoint::in_memory();
+        runtime::spawn(async move {
+            let socket = incoming.next().await.unwrap().unwrap();
+            let mut connection = server::handshake(socket).await.unwrap();
+            while let Some(Ok((_, mut respond))) = connection.accept().await {
+                let response = HttpResponse::builder()
+                    .header("content-type", "application/grpc")
+                    .body(())
+                    .unwrap();
+                let mut body = respond.send_response(response, false).unwrap();
+                let _ = body.send_data(Bytes::from_static(OVERSIZED), false);
+            }
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        let status = client.say_echo(request(1)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    });
+}
+
+#[test]
+fn limits_apply_to_messages_of_real_calls() {
+    runtime::block_on(async {
+        // 4 MiB by default
+        let endpoint = serve(Server::builder(), EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint);
+        assert!(client.say_echo(request(1024 * 1024)).await.is_ok());
+        let status = client.say_echo(request(5 * 1024 * 1024)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        let server = Server::builder().max_decoding_message_size(64);
+        let endpoint = serve(server, EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint);
+        assert!(client.say_echo(request(32)).await.is_ok());
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        // a generated server's own limit wins over the server's
+        let server = Server::builder().max_decoding_message_size(64);
+        let echo = EchoServer::new(MyEcho::default()).max_decoding_message_size(1024);
+        let mut client = EchoClient::new(serve(server, echo));
+        assert!(client.say_echo(request(128)).await.is_ok());
+    });
+}
+
+#[test]
+fn clients_limit_the_responses_they_accept() {
+    runtime::block_on(async {
+        let endpoint = serve(Server::builder(), EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint.max_decoding_message_size(64));
+        assert!(client.say_echo(request(32)).await.is_ok());
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        let endpoint = serve(Server::builder(), EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint).max_decoding_message_size(64);
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    });
+}
+
+#[test]
+fn encoding_limits_fail_calls_with_resource_exhausted() {
+    runtime::block_on(async {
+        // the client refuses to send a request over its limit
+        let endpoint = serve(Server::builder(), EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint).max_encoding_message_size(64);
+        assert!(client.say_echo(request(32)).await.is_ok());
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        // and the server a response over its limit
+        let server = Server::builder().max_encoding_message_size(64);
+        let mut client = EchoClient::new(serve(server, EchoServer::new(MyEcho::default())));
+        assert!(client.say_echo(request(32)).await.is_ok());
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        let echo = EchoServer::new(MyEcho::default()).max_encoding_message_size(64);
+        let mut client = EchoClient::new(serve(Server::builder(), echo));
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    });
+}
+
+#[test]
+fn size_errors_name_the_limit_in_both_directions() {
+    runtime::block_on(async {
+        let server = Server::builder().max_decoding_message_size(64);
+        let mut client = EchoClient::new(serve(server, EchoServer::new(MyEcho::default())));
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(status
+            .message()
+            .ends_with("larger than the limit of 64 bytes"));
+
+        let endpoint = serve(Server::builder(), EchoServer::new(MyEcho::default()));
+        let mut client = EchoClient::new(endpoint).max_encoding_message_size(64);
+        let status = client.say_echo(request(128)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(status
+            .message()
+            .ends_with("larger than the limit of 64 bytes"));
+    });
+}
@@ -0,0 +1,123 @@
+use cynthia::runtime;
+use lucat::common::{Request, Response, Status};
+use lucat::metadata::{BinaryMetadataValue, MetadataValue};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct Mirror {}
+
+// Answers with the request metadata it was sent, under other keys.
+#[lucat::async_trait]
+impl Echo for Mirror {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let user = request
+            .metadata()
+            .get("x-user")
+            .cloned()
+            .ok_or_else(|| Status::invalid_argument("missing x-user"))?;
+        let token = request
+            .metadata()
+            .get_bin("x-token-bin")
+            .cloned()
+            .ok_or_else(|| Status::invalid_argument("missing x-token-bin"))?;
+
+        let request = request.into_inner();
+        let mut response = Response::new(EchoResponse {
+            data: request.data,
+            tag: request.tag,
+            name: request.name,
+        });
+        response.metadata_mut().insert("x-seen-user", user);
+        response
+            .metadata_mut()
+            .insert_bin("x-seen-token-bin", token);
+        Ok(response)
+    }
+}
+
+fn request(token: &[u8]) -> Request<EchoRequest> {
+    let mut request = Request::new(EchoRequest {
+        data: b"hello".to_vec(),
+        tag: vec![],
+        name: None,
+    });
+    request
+        .metadata_mut()
+        .insert("x-user", MetadataValue::from_static("alice"));
+    request
+        .metadata_mut()
+        .insert_bin("x-token-bin", BinaryMetadataValue::from_bytes(token));
+    request
+}
+
+#[test]
+fn metadata_reaches_the_server_and_comes_back() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(Mirror::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        // binary values of every length modulo 3, so some need padding
+        for token in [&b"\x00"[..], b"\xff\x01", b"\x00\x80\xfe"] {
+            let response = client.say_echo(request(token)).await.unwrap();
+            let metadata = response.metadata();
+            assert_eq!(metadata.get("x-seen-user").unwrap(), "alice");
+            assert_eq!(
+                metadata
+                    .get_bin("x-seen-token-bin")
+                    .unwrap()
+                    .to_bytes()
+                    .unwrap(),
+                token
+            );
+            assert!(metadata.get("content-type").is_some());
+            assert!(metadata.get("grpc-status").is_none());
+            assert_eq!(response.into_inner().data, b"hello");
+        }
+    });
+}
+
+#[test]
+fn missing_metadata_is_seen_by_the_server() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(Mirror::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        let status = client
+            .say_echo(Request::new(EchoRequest {
+                data: vec![],
+                tag: vec![],
+                name: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "missing x-user");
+    });
+}
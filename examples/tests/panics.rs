@@ -0,0 +1,72 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::{self, StreamExt};
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::{BytesCodec, Streaming};
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+
+async fn call(endpoint: Endpoint, path: &'static str) -> Result<Vec<Bytes>, Status> {
+    let mut rpc = Rpc::new(endpoint);
+    let frames = stream::iter(vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")]);
+    let path = PathAndQuery::from_static(path);
+    let mut replies = rpc
+        .streaming(Request::new(frames), path, BytesCodec)
+        .await?
+        .into_inner();
+
+    let mut messages = Vec::new();
+    while let Some(message) = replies.message().await? {
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+#[test]
+fn panicking_handlers_only_fail_their_own_call() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let fallback = |path: PathAndQuery, request: Request<Streaming<Bytes>>| async move {
+            match path.path() {
+                "/panic.Service/Call" => panic!("handler gave up"),
+                "/panic.Service/Stream" => {
+                    let frames = request.into_inner().map(|frame| -> Result<Bytes, Status> {
+                        match frame? {
+                            frame if frame == "b" => panic!("stream gave up"),
+                            frame => Ok(frame),
+                        }
+                    });
+                    Ok(Response::new(frames.boxed()))
+                }
+                _ => Ok(Response::new(request.into_inner().boxed())),
+            }
+        };
+
+        let router = Server::builder().fallback(fallback);
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let status = call(endpoint.clone(), "/panic.Service/Call")
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+
+        let status = call(endpoint.clone(), "/panic.Service/Stream")
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+
+        // the connection outlives the panics
+        let messages = call(endpoint, "/echo.Echo/Echo").await.unwrap();
+        assert_eq!(
+            messages,
+            vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")]
+        );
+    });
+}
@@ -0,0 +1,153 @@
+use bytes::Bytes;
+use cynthia::io::Timer;
+use cynthia::runtime;
+use cynthia::runtime::stream;
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::{BytesCodec, Streaming};
+use lucat::common::{Code, Request, Response, Status};
+use lucat::metadata::{MetadataMap, MetadataValue};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use std::time::Duration;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    // answers with the timeout the call arrived with as its tag, and the
+    // length of the data as a trailer; empty data fails the call
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let mut cost = MetadataMap::new();
+        cost.insert("x-cost", request.get_ref().data.len().to_string().parse().unwrap());
+        if request.get_ref().data.is_empty() {
+            return Err(Status::with_metadata(Code::InvalidArgument, "no data", cost));
+        }
+
+        let timeout = request
+            .metadata()
+            .get("grpc-timeout")
+            .map(|timeout| timeout.as_bytes().to_vec())
+            .unwrap_or_default();
+        let r = request.into_inner();
+        let mut response = Response::new(EchoResponse {
+            data: r.data,
+            tag: timeout,
+            name: r.name,
+        });
+        *response.trailers_mut() = cost;
+        Ok(response)
+    }
+}
+
+// Serves `proxy` in front of an echo server and a server that never answers.
+fn start() -> Endpoint {
+    let (echo, echo_incoming) = Endpoint::in_memory();
+    let router = Server::builder().register(EchoServer::new(MyEcho::default()));
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(echo_incoming).await;
+    })
+    .detach();
+
+    let (slow, slow_incoming) = Endpoint::in_memory();
+    let router = Server::builder().fallback(|_: PathAndQuery, request: Request<Streaming<Bytes>>| async move {
+        Timer::after(Duration::from_secs(10)).await;
+        Ok(Response::new(request.into_inner()))
+    });
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(slow_incoming).await;
+    })
+    .detach();
+
+    let proxy = lucat::proxy::configure()
+        .route("/echo.Echo/", echo)
+        .route("/slow.", slow)
+        .build();
+    let (endpoint, incoming) = Endpoint::in_memory();
+    let router = Server::builder().fallback(proxy);
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    endpoint
+}
+
+#[test]
+fn calls_reach_the_upstream_with_the_remaining_deadline() {
+    runtime::block_on(async {
+        let mut client = EchoClient::new(start());
+
+        let mut request = Request::new(EchoRequest {
+            data: vec![1, 2, 3],
+            tag: vec![],
+            name: Some(7),
+        });
+        request
+            .metadata_mut()
+            .insert("grpc-timeout", MetadataValue::from_static("5S"));
+        let response = client.say_echo(request).await.unwrap().into_inner();
+        assert_eq!(response.data, vec![1, 2, 3]);
+        assert_eq!(response.name, Some(7));
+
+        // five seconds minus the time spent in the proxy, in microseconds
+        let timeout = String::from_utf8(response.tag).unwrap();
+        let micros: u64 = timeout.strip_suffix('u').unwrap().parse().unwrap();
+        assert!(micros > 4_000_000 && micros <= 5_000_000, "{}", timeout);
+    });
+}
+
+#[test]
+fn expired_deadlines_and_unknown_routes_fail() {
+    runtime::block_on(async {
+        let mut rpc = Rpc::new(start());
+
+        let mut request = Request::new(stream::iter(vec![Bytes::from_static(b"ping")]));
+        request
+            .metadata_mut()
+            .insert("grpc-timeout", MetadataValue::from_static("50m"));
+        let path = PathAndQuery::from_static("/slow.Service/Call");
+        let response = rpc.streaming(request, path, BytesCodec).await.unwrap();
+        let status = response.into_inner().message().await.unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+
+        let request = Request::new(stream::iter(vec![Bytes::from_static(b"ping")]));
+        let path = PathAndQuery::from_static("/unknown.Service/Call");
+        let response = rpc.streaming(request, path, BytesCodec).await.unwrap();
+        let status = response.into_inner().message().await.unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+    });
+}
+
+#[test]
+fn upstream_trailers_pass_through() {
+    runtime::block_on(async {
+        let mut client = EchoClient::new(start());
+
+        let request = Request::new(EchoRequest {
+            data: vec![1, 2, 3, 4, 5],
+            tag: vec![],
+            name: None,
+        });
+        let response = client.say_echo(request).await.unwrap();
+        assert_eq!(response.trailers().get("x-cost").unwrap(), "5");
+
+        let request = Request::new(EchoRequest {
+            data: vec![],
+            tag: vec![],
+            name: None,
+        });
+        let status = client.say_echo(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.metadata().get("x-cost").unwrap(), "0");
+    });
+}
@@ -0,0 +1,119 @@
+use cynthia::runtime::{self, stream};
+use lucat::common::{Code, Request, Response, Status};
+use lucat::reflection;
+use lucat::reflection::pb::server_reflection_client::ServerReflectionClient;
+use lucat::reflection::pb::server_reflection_request::MessageRequest;
+use lucat::reflection::pb::server_reflection_response::MessageResponse;
+use lucat::reflection::pb::{ServerReflectionRequest, ServerReflectionResponse};
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Streaming;
+use prost::Message;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = lucat::include_file_descriptor_set!("echo_descriptor");
+}
+
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+fn reflection_request(request: MessageRequest) -> ServerReflectionRequest {
+    ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    }
+}
+
+fn file_names(response: MessageResponse) -> Vec<String> {
+    match response {
+        MessageResponse::FileDescriptorResponse(response) => response
+            .file_descriptor_proto
+            .iter()
+            .map(|file| {
+                prost_types::FileDescriptorProto::decode(&file[..])
+                    .unwrap()
+                    .name()
+                    .to_string()
+            })
+            .collect(),
+        other => panic!("unexpected response {:?}", other),
+    }
+}
+
+async fn next(responses: &mut Streaming<ServerReflectionResponse>) -> MessageResponse {
+    let response = responses.message().await.unwrap().unwrap();
+    assert!(response.original_request.is_some());
+    response.message_response.unwrap()
+}
+
+#[test]
+fn reflection_lists_and_resolves() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+        let reflection = reflection::configure()
+            .register_encoded_file_descriptor_set(echo::FILE_DESCRIPTOR_SET)
+            .build()
+            .unwrap();
+
+        let mut server = Server::builder();
+        let router = server
+            .register(EchoServer::new(MyEcho::default()))
+            .add_service(reflection);
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let requests = stream::iter(vec![
+            reflection_request(MessageRequest::ListServices(String::new())),
+            reflection_request(MessageRequest::FileContainingSymbol(
+                "echo.Echo.SayEcho".to_string(),
+            )),
+            reflection_request(MessageRequest::FileByFilename("echo.proto".to_string())),
+            reflection_request(MessageRequest::FileContainingSymbol(
+                "echo.Missing".to_string(),
+            )),
+        ]);
+
+        let mut client = ServerReflectionClient::new(endpoint);
+        let mut responses = client.server_reflection_info(requests).await.unwrap().into_inner();
+
+        match next(&mut responses).await {
+            MessageResponse::ListServicesResponse(list) => {
+                let mut names: Vec<_> = list.service.into_iter().map(|s| s.name).collect();
+                names.sort();
+                assert_eq!(names, ["echo.Echo", "grpc.reflection.v1alpha.ServerReflection"]);
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        assert_eq!(file_names(next(&mut responses).await), ["echo.proto"]);
+        assert_eq!(file_names(next(&mut responses).await), ["echo.proto"]);
+
+        match next(&mut responses).await {
+            MessageResponse::ErrorResponse(error) => {
+                assert_eq!(error.error_code, Code::NotFound as i32);
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        assert!(responses.message().await.unwrap().is_none());
+    });
+}
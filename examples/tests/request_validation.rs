@@ -0,0 +1,142 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::{self, StreamExt};
+use http::uri::PathAndQuery;
+use http::{HeaderMap, Method, Request as HttpRequest, StatusCode};
+use lucat::codec::Streaming;
+use lucat::common::{Request, Response, Status};
+use lucat::transport::duplex;
+use lucat::transport::server::Server;
+use nephele::proto::h2::client;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Sends `request` straight over HTTP/2 and returns the response head, so that
+// it can break the rules a gRPC client keeps.
+async fn send(reached: Arc<AtomicUsize>, request: HttpRequest<()>) -> (StatusCode, HeaderMap) {
+    let (client_io, server_io) = duplex(64 * 1024);
+
+    let fallback = move |_: PathAndQuery, _: Request<Streaming<Bytes>>| {
+        reached.fetch_add(1, Ordering::SeqCst);
+        async move { Err::<Response<Streaming<Bytes>>, _>(Status::not_found("reached")) }
+    };
+    let router = Server::builder().fallback(fallback);
+    runtime::spawn(async move {
+        // the listener stays open, or the server would close the connection
+        let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    let (mut client, connection) = client::handshake(client_io).await.unwrap();
+    runtime::spawn(async move {
+        let _ = connection.await;
+    })
+    .detach();
+
+    let (response, _) = client.send_request(request, true).unwrap();
+    let response = response.await.unwrap();
+    (response.status(), response.headers().clone())
+}
+
+fn request(method: Method, path: &str, content_type: Option<&str>, te: bool) -> HttpRequest<()> {
+    let mut request = HttpRequest::builder()
+        .method(method)
+        .uri(format!("http://localhost{}", path));
+    if let Some(content_type) = content_type {
+        request = request.header("content-type", content_type);
+    }
+    if te {
+        request = request.header("te", "trailers");
+    }
+    request.body(()).unwrap()
+}
+
+#[test]
+fn malformed_requests_never_reach_services() {
+    runtime::block_on(async {
+        let reached = Arc::new(AtomicUsize::new(0));
+        let cases = vec![
+            (
+                request(
+                    Method::GET,
+                    "/echo.Echo/SayEcho",
+                    Some("application/grpc"),
+                    true,
+                ),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "13",
+            ),
+            (
+                request(
+                    Method::POST,
+                    "/echo.Echo/SayEcho",
+                    Some("application/json"),
+                    true,
+                ),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "13",
+            ),
+            (
+                request(
+                    Method::POST,
+                    "/echo.Echo/SayEcho",
+                    Some("application/grpcx"),
+                    true,
+                ),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "13",
+            ),
+            (
+                request(Method::POST, "/echo.Echo/SayEcho", None, true),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "13",
+            ),
+            (
+                request(
+                    Method::POST,
+                    "/echo.Echo/SayEcho",
+                    Some("application/grpc"),
+                    false,
+                ),
+                StatusCode::BAD_REQUEST,
+                "13",
+            ),
+            (
+                request(Method::POST, "/echo.Echo", Some("application/grpc"), true),
+                StatusCode::OK,
+                "12",
+            ),
+            (
+                request(
+                    Method::POST,
+                    "/echo.Echo/Say/Echo",
+                    Some("application/grpc"),
+                    true,
+                ),
+                StatusCode::OK,
+                "12",
+            ),
+        ];
+
+        for (request, http_status, grpc_status) in cases {
+            let description = format!("{} {:?}", request.method(), request);
+            let (status, headers) = send(reached.clone(), request).await;
+            assert_eq!(status, http_status, "for {}", description);
+            assert_eq!(headers["grpc-status"], grpc_status, "for {}", description);
+        }
+        assert_eq!(reached.load(Ordering::SeqCst), 0);
+
+        let valid = request(
+            Method::POST,
+            "/echo.Echo/SayEcho",
+            Some("application/grpc+proto"),
+            true,
+        );
+        let (status, headers) = send(reached.clone(), valid).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["grpc-status"], "5");
+        assert_eq!(reached.load(Ordering::SeqCst), 1);
+    });
+}
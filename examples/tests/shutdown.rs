@@ -0,0 +1,192 @@
+use bytes::{Buf, Bytes};
+use cynthia::io::Timer;
+use cynthia::platform::lock::Barrier;
+use cynthia::runtime::stream::{self, StreamExt};
+use cynthia::runtime::{self, channel, future};
+use http::Request as HttpRequest;
+use lucat::common::{Request, Response, Status};
+use lucat::transport::server::Server;
+use lucat::transport::{duplex, Endpoint};
+use nephele::proto::h2::client::{self, SendRequest};
+use prost::Message;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+// Calls with data "block" meet the test on `entered` and wait until it lets
+// them go through `release`, anything else is echoed at once.
+#[derive(Clone)]
+struct MyEcho {
+    calls: Arc<AtomicUsize>,
+    entered: Arc<Barrier>,
+    release: Arc<Barrier>,
+}
+
+impl MyEcho {
+    fn new() -> Self {
+        MyEcho {
+            calls: Arc::new(AtomicUsize::new(0)),
+            entered: Arc::new(Barrier::new(2)),
+            release: Arc::new(Barrier::new(2)),
+        }
+    }
+}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let r = request.into_inner();
+        if r.data == b"block" {
+            self.entered.wait().await;
+            self.release.wait().await;
+        }
+        Ok(Response::new(EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        }))
+    }
+}
+
+// Starts a unary call straight over HTTP/2, to watch the stream itself.
+fn start(
+    client: &mut SendRequest<Bytes>,
+    data: &[u8],
+) -> Result<client::ResponseFuture, nephele::proto::h2::Error> {
+    let request = HttpRequest::post("http://localhost/echo.Echo/SayEcho")
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(())
+        .unwrap();
+    let (response, mut stream) = client.send_request(request, false)?;
+
+    let message = EchoRequest {
+        data: data.to_vec(),
+        ..EchoRequest::default()
+    };
+    let frame = lucat::codec::encode_frame(Bytes::from(message.encode_to_vec())).unwrap();
+    stream.send_data(frame, true)?;
+    Ok(response)
+}
+
+async fn finish(response: client::ResponseFuture) -> Result<EchoResponse, nephele::proto::h2::Error> {
+    let mut body = response.await?.into_body();
+    let mut frame = Vec::new();
+    while let Some(data) = body.data().await {
+        frame.extend_from_slice(&data?);
+    }
+    let trailers = body.trailers().await?.unwrap_or_default();
+    assert_eq!(trailers["grpc-status"], "0");
+
+    let mut frame = &frame[..];
+    frame.advance(5);
+    Ok(EchoResponse::decode(frame).unwrap())
+}
+
+#[test]
+fn accept_errors_do_not_stop_the_server() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+        let failed = stream::once(Err(io::Error::other("too many open files")));
+        let incoming = failed.chain(incoming);
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(MyEcho::new()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        let request = EchoRequest {
+            data: b"after".to_vec(),
+            ..EchoRequest::default()
+        };
+        let reply = client.say_echo(Request::new(request)).await.unwrap();
+        assert_eq!(reply.into_inner().data, b"after");
+    });
+}
+
+#[test]
+fn shutdown_finishes_in_flight_calls_and_refuses_new_streams() {
+    runtime::block_on(async {
+        let (client_io, server_io) = duplex(64 * 1024);
+        let (shutdown, signal) = channel::bounded::<()>(1);
+
+        let echo = MyEcho::new();
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(echo.clone()));
+        let served = runtime::spawn(async move {
+            // the listener stays open, only the signal ends serving
+            let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+            let signal = async move {
+                let _ = signal.recv().await;
+            };
+            router.serve_with_incoming_shutdown(incoming, signal).await
+        });
+
+        let (mut client, connection) = client::handshake(client_io).await.unwrap();
+        runtime::spawn(async move {
+            let _ = connection.await;
+        })
+        .detach();
+
+        let in_flight = start(&mut client, b"block").unwrap();
+        echo.entered.wait().await;
+
+        // give the server time for both GOAWAY frames of a graceful shutdown
+        shutdown.send(()).await.unwrap();
+        Timer::after(Duration::from_millis(100)).await;
+
+        // the client saw the GOAWAY and opens no new streams
+        assert!(start(&mut client, b"late").is_err());
+
+        echo.release.wait().await;
+        assert_eq!(finish(in_flight).await.unwrap().data, b"block");
+        assert_eq!(echo.calls.load(Ordering::SeqCst), 1);
+
+        served.await.unwrap();
+    });
+}
+
+#[test]
+fn silent_connections_do_not_hold_up_shutdown() {
+    runtime::block_on(async {
+        let (client_io, server_io) = duplex(64 * 1024);
+        let (shutdown, signal) = channel::bounded::<()>(1);
+
+        let mut server = Server::builder().drain_timeout(Duration::from_millis(100));
+        let router = server.register(EchoServer::new(MyEcho::new()));
+        let served = runtime::spawn(async move {
+            let incoming = stream::once(Ok::<_, io::Error>(server_io)).chain(stream::pending());
+            let signal = async move {
+                let _ = signal.recv().await;
+            };
+            router.serve_with_incoming_shutdown(incoming, signal).await
+        });
+
+        // the client connects but never sends the HTTP/2 preface
+        Timer::after(Duration::from_millis(50)).await;
+        shutdown.send(()).await.unwrap();
+
+        let timeout = async {
+            Timer::after(Duration::from_secs(2)).await;
+            panic!("shutdown waited on a silent connection");
+        };
+        future::or(served, timeout).await.unwrap();
+        drop(client_io);
+    });
+}
@@ -0,0 +1,57 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream;
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::{BytesCodec, Streaming};
+use lucat::common::{Request, Response, Status};
+use lucat::status_details;
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+use std::time::Duration;
+
+#[test]
+fn field_violations_reach_the_client() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let fallback = |_: PathAndQuery, _: Request<Streaming<Bytes>>| async move {
+            let status = status_details::builder(Code::InvalidArgument, "bad request")
+                .field_violation("name", "must not be empty")
+                .field_violation("age", "must be positive")
+                .retry_info(Duration::from_secs(3))
+                .localized_message("en-US", "Please fix the form")
+                .build();
+            Err::<Response<Streaming<Bytes>>, _>(status)
+        };
+
+        let mut server = Server::builder();
+        let router = server.fallback(fallback);
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut rpc = Rpc::new(endpoint);
+        let frames = stream::iter(vec![Bytes::from_static(b"{}")]);
+        let path = PathAndQuery::from_static("/users.Users/Create");
+        let status: Status = match rpc.streaming(Request::new(frames), path, BytesCodec).await {
+            Ok(response) => response.into_inner().message().await.unwrap_err(),
+            Err(status) => status,
+        };
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let details = status_details::extract(&status).unwrap();
+        let violations = details.field_violations();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].field, "name");
+        assert_eq!(violations[1].description, "must be positive");
+        assert_eq!(details.retry_delay(), Some(Duration::from_secs(3)));
+        assert_eq!(
+            details.localized_message().unwrap().message,
+            "Please fix the form"
+        );
+        assert!(details.error_info().is_none());
+    });
+}
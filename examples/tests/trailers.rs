@@ -0,0 +1,95 @@
+use cynthia::runtime::{self, stream};
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::ProstCodec;
+use lucat::common::{Request, Response, Status};
+use lucat::metadata::MetadataValue;
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct Metered {}
+
+#[lucat::async_trait]
+impl Echo for Metered {
+    async fn say_echo(
+        &self,
+        request: Request<EchoRequest>,
+    ) -> Result<Response<EchoResponse>, Status> {
+        let request = request.into_inner();
+        let cost = request.data.len().to_string();
+        let mut response = Response::new(EchoResponse {
+            data: request.data,
+            tag: request.tag,
+            name: request.name,
+        });
+        response
+            .metadata_mut()
+            .insert("x-region", MetadataValue::from_static("eu"));
+        response
+            .trailers_mut()
+            .insert("x-cost", cost.parse().unwrap());
+        Ok(response)
+    }
+}
+
+fn request() -> EchoRequest {
+    EchoRequest {
+        data: b"hello".to_vec(),
+        tag: vec![],
+        name: None,
+    }
+}
+
+fn serve() -> Endpoint {
+    let (endpoint, incoming) = Endpoint::in_memory();
+
+    let mut server = Server::builder();
+    let router = server.register(EchoServer::new(Metered::default()));
+    runtime::spawn(async move {
+        let _ = router.serve_with_incoming(incoming).await;
+    })
+    .detach();
+
+    endpoint
+}
+
+#[test]
+fn unary_responses_carry_trailers() {
+    runtime::block_on(async {
+        let mut client = EchoClient::new(serve());
+        let response = client.say_echo(Request::new(request())).await.unwrap();
+
+        assert_eq!(response.trailers().get("x-cost").unwrap(), "5");
+        assert!(response.trailers().get("x-region").is_none());
+        assert!(response.trailers().get("grpc-status").is_none());
+        assert_eq!(response.metadata().get("x-region").unwrap(), "eu");
+    });
+}
+
+#[test]
+fn streams_expose_trailers_once_read() {
+    runtime::block_on(async {
+        let mut rpc = Rpc::new(serve());
+        let path = PathAndQuery::from_static("/echo.Echo/SayEcho");
+        let requests = Request::new(stream::iter(vec![request()]));
+        let codec = ProstCodec::<EchoRequest, EchoResponse>::default();
+
+        let response = rpc.streaming(requests, path, codec).await.unwrap();
+        assert!(response.metadata().get("x-cost").is_none());
+
+        let mut responses = response.into_inner();
+        assert!(responses.trailers().is_none());
+        assert_eq!(responses.message().await.unwrap().unwrap().data, b"hello");
+        assert!(responses.message().await.unwrap().is_none());
+        assert_eq!(responses.trailers().unwrap().get("x-cost").unwrap(), "5");
+    });
+}
@@ -0,0 +1,129 @@
+use bytes::Bytes;
+use cynthia::runtime;
+use cynthia::runtime::stream::StreamExt;
+use http::{HeaderMap, Response as HttpResponse};
+use lucat::common::{Request, Response, Status};
+use lucat::metadata::MetadataMap;
+use lucat::transport::server::Server;
+use lucat::transport::Endpoint;
+use lucat::Code;
+use nephele::proto::h2::server;
+use prost::Message;
+use std::error::Error;
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::echo_client::EchoClient;
+use echo::echo_server::{Echo, EchoServer};
+use echo::{EchoRequest, EchoResponse};
+
+#[derive(Default, Clone)]
+struct Refusing {}
+
+#[lucat::async_trait]
+impl Echo for Refusing {
+    async fn say_echo(&self, _: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("retry-after", "3".parse().unwrap());
+        Err(Status::with_metadata(
+            Code::InvalidArgument,
+            "data must not be empty",
+            metadata,
+        ))
+    }
+}
+
+// Answers every call with `replies` messages and an OK status, however many
+// the method is meant to return.
+fn replying(replies: usize) -> Endpoint {
+    let (endpoint, mut incoming) = Endpoint::in_memory();
+    runtime::spawn(async move {
+        while let Some(Ok(socket)) = incoming.next().await {
+            runtime::spawn(async move {
+                let mut connection = server::handshake(socket).await.unwrap();
+                while let Some(Ok((_, mut respond))) = connection.accept().await {
+                    let response = HttpResponse::builder()
+                        .header("content-type", "application/grpc")
+                        .body(())
+                        .unwrap();
+                    let mut body = respond.send_response(response, false).unwrap();
+                    for _ in 0..replies {
+                        let reply = EchoResponse::default().encode_to_vec();
+                        let frame = lucat::codec::encode_frame(Bytes::from(reply)).unwrap();
+                        body.send_data(frame, false).unwrap();
+                    }
+                    let mut trailers = HeaderMap::new();
+                    trailers.insert("grpc-status", "0".parse().unwrap());
+                    body.send_trailers(trailers).unwrap();
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+    endpoint
+}
+
+fn request() -> Request<EchoRequest> {
+    Request::new(EchoRequest {
+        data: vec![],
+        tag: vec![],
+        name: None,
+    })
+}
+
+#[test]
+fn server_statuses_pass_through_unchanged() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+
+        let mut server = Server::builder();
+        let router = server.register(EchoServer::new(Refusing::default()));
+        runtime::spawn(async move {
+            let _ = router.serve_with_incoming(incoming).await;
+        })
+        .detach();
+
+        let mut client = EchoClient::new(endpoint);
+        let status = client.say_echo(request()).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "data must not be empty");
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "3");
+    });
+}
+
+#[test]
+fn transport_errors_keep_their_source() {
+    runtime::block_on(async {
+        let (endpoint, incoming) = Endpoint::in_memory();
+        drop(incoming);
+
+        let mut client = EchoClient::new(endpoint);
+        let status = client.say_echo(request()).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(status.source().is_some());
+    });
+}
+
+#[test]
+fn unary_calls_reject_more_than_one_response() {
+    runtime::block_on(async {
+        let mut client = EchoClient::new(replying(2));
+        let status = client.say_echo(request()).await.unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+
+        let mut client = EchoClient::new(replying(1));
+        assert!(client.say_echo(request()).await.is_ok());
+    });
+}
+
+#[test]
+fn unary_calls_reject_an_ok_status_without_a_response() {
+    runtime::block_on(async {
+        let mut client = EchoClient::new(replying(0));
+        let status = client.say_echo(request()).await.unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+    });
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::os::unix::net::UnixListener;
+use cynthia::runtime::Async;
+
+use lucat::transport::server::Server;
+use lucat::common::{Request, Response, Status};
+
+pub mod echo {
+    lucat::include_proto!("echo");
+}
+
+use echo::{EchoRequest, EchoResponse};
+use echo::echo_server::{Echo, EchoServer};
+
+const SOCKET_PATH: &str = "/tmp/lucat-echo.sock";
+
+#[cynthia::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let _ = std::fs::remove_file(SOCKET_PATH);
+    let listener = Async::<UnixListener>::bind(SOCKET_PATH)?;
+
+    let mut server = Server::builder();
+    let route = server.register(EchoServer::new(MyEcho::default()));
+    println!("server listen on unix:{}", SOCKET_PATH);
+
+    route.serve_with_incoming(listener.incoming()).await?;
+
+    Ok(())
+}
+
+#[derive(Default, Clone)]
+pub struct MyEcho {}
+
+#[lucat::async_trait]
+impl Echo for MyEcho {
+    async fn say_echo(
+        &self, request: Request<EchoRequest>
+    ) -> Result<Response<EchoResponse>, Status> {
+        let r = request.into_inner();
+        println!("recved: {:?}", r);
+        let reply = EchoResponse {
+            data: r.data,
+            tag: r.tag,
+            name: r.name,
+        };
+
+        Ok(Response::new(reply))
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Attributes, Method, Service};
+use super::service::method_codec_paths;
 use super::{generate_doc_comments, naive_snake_case};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -8,11 +9,18 @@ pub fn generate<T: Service>(
     emit_package: bool,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_paths: &[String],
     attributes: &Attributes,
 ) -> TokenStream {
     let service_ident = quote::format_ident!("{}Client", service.name());
-    let client_mod = quote::format_ident!("{}_client", naive_snake_case(&service.name()));
-    let methods = generate_methods(service, emit_package, proto_path, compile_well_known_types);
+    let client_mod = quote::format_ident!("{}_client", naive_snake_case(service.name()));
+    let methods = generate_methods(
+        service,
+        emit_package,
+        proto_path,
+        compile_well_known_types,
+        codec_paths,
+    );
 
     let connect = generate_connect(&service_ident);
     let service_doc = generate_doc_comments(service.comment());
@@ -55,6 +63,16 @@ pub fn generate<T: Service>(
                     Self { inner }
                 }
 
+                pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+                    self.inner = self.inner.max_decoding_message_size(limit);
+                    self
+                }
+
+                pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+                    self.inner = self.inner.max_encoding_message_size(limit);
+                    self
+                }
+
                 #methods
             }
         }
@@ -83,11 +101,12 @@ fn generate_methods<T: Service>(
     emit_package: bool,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_paths: &[String],
 ) -> TokenStream {
     let mut stream = TokenStream::new();
     let package = if emit_package { service.package() } else { "" };
 
-    for method in service.methods() {
+    for (method, codec_path) in method_codec_paths(service, codec_paths) {
         let path = format!(
             "/{}{}{}/{}",
             package,
@@ -99,14 +118,26 @@ fn generate_methods<T: Service>(
         stream.extend(generate_doc_comments(method.comment()));
 
         let method = match (method.client_streaming(), method.server_streaming()) {
-            (false, false) => generate_unary(method, proto_path, compile_well_known_types, path),
-            (false, true) => {
-                generate_server_streaming(method, proto_path, compile_well_known_types, path)
+            (false, false) => {
+                generate_unary(method, proto_path, compile_well_known_types, codec_path, path)
             }
-            (true, false) => {
-                generate_client_streaming(method, proto_path, compile_well_known_types, path)
+            (false, true) => generate_server_streaming(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                path,
+            ),
+            (true, false) => generate_client_streaming(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                path,
+            ),
+            (true, true) => {
+                generate_streaming(method, proto_path, compile_well_known_types, codec_path, path)
             }
-            (true, true) => generate_streaming(method, proto_path, compile_well_known_types, path),
         };
 
         stream.extend(method);
@@ -119,9 +150,10 @@ fn generate_unary<T: Method>(
     method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
-    _path: String,
+    codec_path: &str,
+    path: String,
 ) -> TokenStream {
-    let _codec_name = syn::parse_str::<syn::Path>(T::CODEC_PATH).unwrap();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
     let ident = format_ident!("{}", method.name());
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
 
@@ -130,8 +162,8 @@ fn generate_unary<T: Method>(
             &mut self,
             request: lucat::Request<#request>,
         ) -> Result<lucat::Response<#response>, lucat::Status> {
-            let codec = lucat::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/helloworld.Greeter/SayHello");
+            let codec = #codec_name::default();
+            let path = http::uri::PathAndQuery::from_static(#path);
             self.inner.unary(request, path, codec).await
         }
     }
@@ -141,9 +173,10 @@ fn generate_server_streaming<T: Method>(
     method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_path: &str,
     path: String,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(T::CODEC_PATH).unwrap();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
     let ident = format_ident!("{}", method.name());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
@@ -153,12 +186,9 @@ fn generate_server_streaming<T: Method>(
             &mut self,
             request: impl lucat::IntoRequest<#request>,
         ) -> Result<lucat::Response<lucat::codec::Streaming<#response>>, lucat::Status> {
-            self.inner.ready().await.map_err(|e| {
-                        lucat::Status::new(lucat::Code::Unknown, format!("Service was not ready: {}", e.into()))
-            })?;
             let codec = #codec_name::default();
-           let path = http::uri::PathAndQuery::from_static(#path);
-           self.inner.server_streaming(request.into_request(), path, codec).await
+            let path = http::uri::PathAndQuery::from_static(#path);
+            self.inner.server_streaming(request.into_request(), path, codec).await
         }
     }
 }
@@ -167,9 +197,10 @@ fn generate_client_streaming<T: Method>(
     method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_path: &str,
     path: String,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(T::CODEC_PATH).unwrap();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
     let ident = format_ident!("{}", method.name());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
@@ -179,9 +210,6 @@ fn generate_client_streaming<T: Method>(
             &mut self,
             request: impl lucat::IntoStreamingRequest<Message = #request>
         ) -> Result<lucat::Response<#response>, lucat::Status> {
-            self.inner.ready().await.map_err(|e| {
-                        lucat::Status::new(lucat::Code::Unknown, format!("Service was not ready: {}", e.into()))
-            })?;
             let codec = #codec_name::default();
             let path = http::uri::PathAndQuery::from_static(#path);
             self.inner.client_streaming(request.into_streaming_request(), path, codec).await
@@ -193,9 +221,10 @@ fn generate_streaming<T: Method>(
     method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_path: &str,
     path: String,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(T::CODEC_PATH).unwrap();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
     let ident = format_ident!("{}", method.name());
 
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
@@ -205,12 +234,9 @@ fn generate_streaming<T: Method>(
             &mut self,
             request: impl lucat::IntoStreamingRequest<Message = #request>
         ) -> Result<lucat::Response<lucat::codec::Streaming<#response>>, lucat::Status> {
-            self.inner.ready().await.map_err(|e| {
-                        lucat::Status::new(lucat::Code::Unknown, format!("Service was not ready: {}", e.into()))
-            })?;
             let codec = #codec_name::default();
-           let path = http::uri::PathAndQuery::from_static(#path);
-           self.inner.streaming(request.into_streaming_request(), path, codec).await
+            let path = http::uri::PathAndQuery::from_static(#path);
+            self.inner.streaming(request.into_streaming_request(), path, codec).await
         }
     }
 }
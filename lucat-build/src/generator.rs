@@ -1,10 +1,13 @@
 use super::{client, server, Attributes};
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
+use prost::Message;
 use prost_build::{Config, Method, Service};
+use prost_types::FileDescriptorSet;
 use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
-use std::io;
 use std::path::{Path, PathBuf};
+use std::{fs, io};
 
 #[derive(Debug, Clone)]
 pub struct Builder {
@@ -14,6 +17,9 @@ pub struct Builder {
     pub(crate) extern_path: Vec<(String, String)>,
     pub(crate) field_attributes: Vec<(String, String)>,
     pub(crate) type_attributes: Vec<(String, String)>,
+    pub(crate) codec_path: Option<String>,
+    pub(crate) service_codec_paths: Vec<(String, String)>,
+    pub(crate) method_codec_paths: Vec<(String, String)>,
     pub(crate) server_attributes: Attributes,
     pub(crate) client_attributes: Attributes,
     pub(crate) proto_path: String,
@@ -73,6 +79,23 @@ impl Builder {
         self
     }
 
+    pub fn codec_path(mut self, codec_path: impl AsRef<str>) -> Self {
+        self.codec_path = Some(checked_codec_path("codec_path", codec_path.as_ref()));
+        self
+    }
+
+    pub fn service_codec_path<P: AsRef<str>, C: AsRef<str>>(mut self, path: P, codec_path: C) -> Self {
+        let codec_path = checked_codec_path("service_codec_path", codec_path.as_ref());
+        self.service_codec_paths.push((path.as_ref().to_string(), codec_path));
+        self
+    }
+
+    pub fn method_codec_path<P: AsRef<str>, C: AsRef<str>>(mut self, path: P, codec_path: C) -> Self {
+        let codec_path = checked_codec_path("method_codec_path", codec_path.as_ref());
+        self.method_codec_paths.push((path.as_ref().to_string(), codec_path));
+        self
+    }
+
     pub fn server_mod_attribute<P: AsRef<str>, A: AsRef<str>>(
         mut self,
         path: P,
@@ -151,9 +174,12 @@ impl Builder {
         let format = self.format;
 
         config.out_dir(out_dir.clone());
-        if let Some(path) = self.file_descriptor_set_path.as_ref() {
-            config.file_descriptor_set_path(path);
-        }
+        // the generated `ReflectMessage` impls embed the descriptors protoc writes here
+        let file_descriptor_set_path = self
+            .file_descriptor_set_path
+            .clone()
+            .unwrap_or_else(|| out_dir.join("lucat_descriptor_set.bin"));
+        config.file_descriptor_set_path(&file_descriptor_set_path);
         for (proto_path, rust_path) in self.extern_path.iter() {
             config.extern_path(proto_path, rust_path);
         }
@@ -171,7 +197,7 @@ impl Builder {
             config.protoc_arg(arg);
         }
 
-        config.service_generator(Box::new(ServiceGenerator::new(self)));
+        config.service_generator(Box::new(ServiceGenerator::new(self, file_descriptor_set_path)));
 
         config.compile_protos(protos, includes)?;
 
@@ -186,6 +212,15 @@ impl Builder {
     }
 }
 
+// Codec paths end up in the generated code, so a bad one is caught where it
+// is configured rather than deep inside code generation.
+fn checked_codec_path(method: &str, codec_path: &str) -> String {
+    if let Err(e) = syn::parse_str::<syn::Path>(codec_path) {
+        panic!("{}: {:?} is not a valid Rust path: {}", method, codec_path, e);
+    }
+    codec_path.to_string()
+}
+
 pub fn configure() -> Builder {
     Builder {
         build_client: true,
@@ -195,6 +230,9 @@ pub fn configure() -> Builder {
         extern_path: Vec::new(),
         field_attributes: Vec::new(),
         type_attributes: Vec::new(),
+        codec_path: None,
+        service_codec_paths: Vec::new(),
+        method_codec_paths: Vec::new(),
         server_attributes: Attributes::default(),
         client_attributes: Attributes::default(),
         proto_path: "super".to_string(),
@@ -219,6 +257,9 @@ pub fn compile_protos(proto: impl AsRef<Path>) -> io::Result<()> {
 }
 
 const PROST_CODEC_PATH: &str = "lucat::codec::ProstCodec";
+// the codecs that map generated messages through their descriptors, and so
+// need `ReflectMessage` impls for them
+const REFLECTING_CODEC_PATHS: &[&str] = &["lucat::codec::JsonCodec"];
 
 impl crate::Service for Service {
     const CODEC_PATH: &'static str = PROST_CODEC_PATH;
@@ -314,28 +355,131 @@ fn is_google_type(ty: &str) -> bool {
 
 struct ServiceGenerator {
     builder: Builder,
+    file_descriptor_set_path: PathBuf,
     clients: TokenStream,
     servers: TokenStream,
+    // `ReflectMessage` impls by package, and the messages they cover
+    messages: HashMap<String, TokenStream>,
+    reflected: HashSet<String>,
 }
 
 impl ServiceGenerator {
-    fn new(builder: Builder) -> Self {
+    fn new(builder: Builder, file_descriptor_set_path: PathBuf) -> Self {
         ServiceGenerator {
             builder,
+            file_descriptor_set_path,
             clients: TokenStream::default(),
             servers: TokenStream::default(),
+            messages: HashMap::new(),
+            reflected: HashSet::new(),
+        }
+    }
+}
+
+impl ServiceGenerator {
+    // The codec of every method of `service`, from the most specific option
+    // that matches it: method, service, then the builder-wide codec path.
+    fn codec_paths(&self, service: &Service) -> Vec<String> {
+        let service_path = format!(
+            "{}{}{}",
+            service.package,
+            if service.package.is_empty() { "" } else { "." },
+            service.proto_name
+        );
+
+        // the last matching option wins
+        let find = |options: &[(String, String)], path: &str| {
+            options
+                .iter()
+                .rev()
+                .find(|(pattern, _)| crate::service::match_name(pattern, path))
+                .map(|(_, codec_path)| codec_path.clone())
+        };
+
+        let service_codec_path = find(&self.builder.service_codec_paths, &service_path)
+            .or_else(|| self.builder.codec_path.clone());
+
+        service
+            .methods
+            .iter()
+            .map(|method| {
+                let method_path = format!("{}.{}", service_path, method.proto_name);
+                find(&self.builder.method_codec_paths, &method_path)
+                    .or_else(|| service_codec_path.clone())
+                    .unwrap_or_else(|| <Method as crate::Method>::CODEC_PATH.to_string())
+            })
+            .collect()
+    }
+
+    // Only the messages of the service's own package get an impl, the others
+    // may be generated by another build.
+    fn reflect_messages(&mut self, service: &Service, codec_paths: &[String]) {
+        let prefix = format!(".{}.", service.package);
+
+        for (method, codec_path) in service.methods.iter().zip(codec_paths) {
+            if !REFLECTING_CODEC_PATHS.contains(&codec_path.trim_start_matches("::")) {
+                continue;
+            }
+
+            let types = [
+                (&method.input_proto_type, &method.input_type),
+                (&method.output_proto_type, &method.output_type),
+            ];
+            for (proto_type, rust_type) in types.iter() {
+                let foreign = ["::", "crate::", "super::"]
+                    .iter()
+                    .any(|prefix| rust_type.starts_with(prefix));
+                if !proto_type.starts_with(&prefix)
+                    || foreign
+                    || !self.reflected.insert(proto_type.to_string())
+                {
+                    continue;
+                }
+
+                let ty = syn::parse_str::<syn::Path>(rust_type).unwrap();
+                let name = proto_type.trim_start_matches('.');
+                let message = quote::quote! {
+                    impl lucat::dynamic::ReflectMessage for #ty {
+                        fn descriptor() -> lucat::dynamic::MessageDescriptor {
+                            descriptor_pool()
+                                .get_message_by_name(#name)
+                                .expect("message missing from the file descriptor set")
+                        }
+                    }
+                };
+                self.messages
+                    .entry(service.package.clone())
+                    .or_default()
+                    .extend(message);
+            }
         }
     }
 }
 
+// the descriptor set protoc wrote, without the comments and source spans
+fn encoded_descriptor_set(path: &Path) -> Vec<u8> {
+    let encoded = fs::read(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+    let mut set = FileDescriptorSet::decode(&encoded[..])
+        .unwrap_or_else(|e| panic!("invalid file descriptor set {}: {}", path.display(), e));
+    for file in set.file.iter_mut() {
+        file.source_code_info = None;
+    }
+    set.encode_to_vec()
+}
+
 impl prost_build::ServiceGenerator for ServiceGenerator {
     fn generate(&mut self, service: prost_build::Service, _buf: &mut String) {
+        let codec_paths = self.codec_paths(&service);
+        self.reflect_messages(&service, &codec_paths);
+
         if self.builder.build_server {
             let server = server::generate(
                 &service,
                 self.builder.emit_package,
                 &self.builder.proto_path,
                 self.builder.compile_well_known_types,
+                &codec_paths,
                 &self.builder.server_attributes,
             );
             self.servers.extend(server);
@@ -347,6 +491,7 @@ impl prost_build::ServiceGenerator for ServiceGenerator {
                 self.builder.emit_package,
                 &self.builder.proto_path,
                 self.builder.compile_well_known_types,
+                &codec_paths,
                 &self.builder.client_attributes,
             );
             self.clients.extend(client);
@@ -380,4 +525,116 @@ impl prost_build::ServiceGenerator for ServiceGenerator {
             self.servers = TokenStream::default();
         }
     }
-}
\ No newline at end of file
+
+    fn finalize_package(&mut self, package: &str, buf: &mut String) {
+        let messages = match self.messages.remove(package) {
+            Some(messages) => messages,
+            None => return,
+        };
+
+        let file_descriptor_set = Literal::byte_string(&encoded_descriptor_set(
+            &self.file_descriptor_set_path,
+        ));
+        let code = quote::quote! {
+            fn descriptor_pool() -> &'static lucat::dynamic::DescriptorPool {
+                static POOL: std::sync::OnceLock<lucat::dynamic::DescriptorPool> =
+                    std::sync::OnceLock::new();
+                POOL.get_or_init(|| {
+                    lucat::dynamic::DescriptorPool::decode(&#file_descriptor_set[..])
+                        .expect("invalid file descriptor set")
+                })
+            }
+
+            #messages
+        };
+        buf.push_str(&code.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{configure, ServiceGenerator, PROST_CODEC_PATH};
+    use crate::service::method_codec_paths;
+    use prost_build::{Comments, Method, Service};
+    use std::path::PathBuf;
+
+    fn comments() -> Comments {
+        Comments {
+            leading_detached: Vec::new(),
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+
+    // echo.Echo with the methods `names`, each taking and returning an EchoRequest
+    fn echo(names: &[&str]) -> Service {
+        let method = |name: &&str| Method {
+            name: name.to_lowercase(),
+            proto_name: name.to_string(),
+            comments: comments(),
+            input_type: "EchoRequest".to_string(),
+            output_type: "EchoRequest".to_string(),
+            input_proto_type: ".echo.EchoRequest".to_string(),
+            output_proto_type: ".echo.EchoRequest".to_string(),
+            options: Default::default(),
+            client_streaming: false,
+            server_streaming: false,
+        };
+        Service {
+            name: "Echo".to_string(),
+            proto_name: "Echo".to_string(),
+            package: "echo".to_string(),
+            comments: comments(),
+            methods: names.iter().map(method).collect(),
+            options: Default::default(),
+        }
+    }
+
+    #[test]
+    fn codec_paths_are_accepted() {
+        let builder = configure()
+            .codec_path("lucat::codec::ProstCodec")
+            .service_codec_path("echo.Echo", "crate::codec::MyCodec")
+            .method_codec_path("echo.Echo.SayEcho", "::my_codecs::JsonCodec");
+        assert_eq!(builder.codec_path.as_deref(), Some("lucat::codec::ProstCodec"));
+        assert_eq!(builder.method_codec_paths[0].1, "::my_codecs::JsonCodec");
+    }
+
+    #[test]
+    #[should_panic(expected = "service_codec_path: \"my codec\" is not a valid Rust path")]
+    fn bad_codec_paths_panic_naming_the_method() {
+        configure().service_codec_path("echo.Echo", "my codec");
+    }
+
+    #[test]
+    fn methods_without_codec_paths_use_their_own() {
+        let service = echo(&["SayEcho", "SayMore"]);
+        let paths: Vec<_> = method_codec_paths(&service, &[]).map(|(_, path)| path).collect();
+        assert_eq!(paths, [PROST_CODEC_PATH, PROST_CODEC_PATH]);
+
+        let overrides = ["a::Codec".to_string(), "b::Codec".to_string()];
+        let paths: Vec<_> = method_codec_paths(&service, &overrides)
+            .map(|(method, path)| (method.proto_name.as_str(), path))
+            .collect();
+        assert_eq!(paths, [("SayEcho", "a::Codec"), ("SayMore", "b::Codec")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 codec paths for the 2 methods of Echo")]
+    fn codec_paths_must_cover_every_method() {
+        let service = echo(&["SayEcho", "SayMore"]);
+        let _ = method_codec_paths(&service, &["a::Codec".to_string()]).count();
+    }
+
+    #[test]
+    fn only_reflecting_codecs_get_reflect_message_impls() {
+        let mut generator = ServiceGenerator::new(configure(), PathBuf::new());
+        let paths = ["crate::codec::ProstLikeCodec".to_string()];
+        generator.reflect_messages(&echo(&["SayEcho"]), &paths);
+        assert!(generator.messages.is_empty());
+
+        let paths = ["::lucat::codec::JsonCodec".to_string()];
+        generator.reflect_messages(&echo(&["SayEcho"]), &paths);
+        assert!(generator.reflected.contains(".echo.EchoRequest"));
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Attributes, Method, Service};
+use super::service::method_codec_paths;
 use super::{generate_doc_comment, generate_doc_comments, naive_snake_case};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
@@ -9,13 +10,20 @@ pub fn generate<T: Service>(
     emit_package: bool,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_paths: &[String],
     attributes: &Attributes,
 ) -> TokenStream {
-    let methods = generate_methods_plain(service, proto_path, compile_well_known_types);
+    let methods = generate_methods(
+        service,
+        emit_package,
+        proto_path,
+        compile_well_known_types,
+        codec_paths,
+    );
 
     let server_service = quote::format_ident!("{}Server", service.name());
     let server_trait = quote::format_ident!("{}", service.name());
-    let server_mod = quote::format_ident!("{}_server", naive_snake_case(&service.name()));
+    let server_mod = quote::format_ident!("{}_server", naive_snake_case(service.name()));
     let generated_trait = generate_trait(
         service,
         proto_path,
@@ -60,6 +68,8 @@ pub fn generate<T: Service>(
             #[derive(Debug)]
             pub struct #server_service<T: #server_trait> {
                 inner: _Inner<T>,
+                max_decoding_message_size: Option<usize>,
+                max_encoding_message_size: Option<usize>,
             }
 
             struct _Inner<T>(Arc<T>);
@@ -70,8 +80,20 @@ pub fn generate<T: Service>(
                     let inner = _Inner(inner);
                     Self {
                         inner,
+                        max_decoding_message_size: None,
+                        max_encoding_message_size: None,
                     }
                 }
+
+                pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+                    self.max_decoding_message_size = Some(limit);
+                    self
+                }
+
+                pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+                    self.max_encoding_message_size = Some(limit);
+                    self
+                }
             }
 
             impl<T: #server_trait> Service<lucat::Request<lucat::Body>> for #server_service<T> {
@@ -82,7 +104,13 @@ pub fn generate<T: Service>(
                 fn call(&mut self, req: lucat::Request<lucat::Body>) -> Self::Future {
                     let inner = self.inner.clone();
 
-                    #methods
+                    match req.extensions().get::<http::uri::PathAndQuery>().map(|path| path.path()) {
+                        #methods
+                        _ => Box::pin(async move {
+                            let status = lucat::Status::unimplemented("method not implemented");
+                            Ok(lucat::Response::new(lucat::Body::error(status)))
+                        }),
+                    }
                 }
             }
 
@@ -91,6 +119,8 @@ pub fn generate<T: Service>(
                     let inner = self.inner.clone();
                     Self {
                         inner,
+                        max_decoding_message_size: self.max_decoding_message_size,
+                        max_encoding_message_size: self.max_encoding_message_size,
                     }
                 }
             }
@@ -119,7 +149,7 @@ fn generate_trait<T: Service>(
     server_trait: Ident,
 ) -> TokenStream {
     let methods = generate_trait_methods(service, proto_path, compile_well_known_types);
-    let trait_doc = generate_doc_comment(&format!(
+    let trait_doc = generate_doc_comment(format!(
         "Generated trait containing gRPC methods that should be implemented for use with {}Server.",
         service.name()
     ));
@@ -165,14 +195,14 @@ fn generate_trait_methods<T: Service>(
             }
             (false, true) => {
                 let stream = quote::format_ident!("{}Stream", method.identifier());
-                let stream_doc = generate_doc_comment(&format!(
+                let stream_doc = generate_doc_comment(format!(
                     "Server streaming response type for the {} method.",
                     method.identifier()
                 ));
 
                 quote! {
                     #stream_doc
-                    type #stream: futures_core::Stream<Item = Result<#res_message, lucat::Status>> + Send + Sync + 'static;
+                    type #stream: Stream<Item = Result<#res_message, lucat::Status>> + Send + 'static;
 
                     #method_doc
                     async fn #name(&self, request: lucat::Request<#req_message>)
@@ -181,14 +211,14 @@ fn generate_trait_methods<T: Service>(
             }
             (true, true) => {
                 let stream = quote::format_ident!("{}Stream", method.identifier());
-                let stream_doc = generate_doc_comment(&format!(
+                let stream_doc = generate_doc_comment(format!(
                     "Server streaming response type for the {} method.",
                     method.identifier()
                 ));
 
                 quote! {
                     #stream_doc
-                    type #stream: futures_core::Stream<Item = Result<#res_message, lucat::Status>> + Send + Sync + 'static;
+                    type #stream: Stream<Item = Result<#res_message, lucat::Status>> + Send + 'static;
 
                     #method_doc
                     async fn #name(&self, request: lucat::Request<lucat::Streaming<#req_message>>)
@@ -203,6 +233,22 @@ fn generate_trait_methods<T: Service>(
     stream
 }
 
+#[cfg(feature = "transport")]
+fn generate_transport(
+    server_service: &syn::Ident,
+    server_trait: &syn::Ident,
+    service_name: &str,
+) -> TokenStream {
+    let service_name = LitStr::new(service_name, Span::call_site());
+
+    quote! {
+        impl<T: #server_trait> lucat::transport::NamedService for #server_service<T> {
+            const NAME: &'static str = #service_name;
+        }
+    }
+}
+
+#[cfg(not(feature = "transport"))]
 fn generate_transport(
     _server_service: &syn::Ident,
     _server_trait: &syn::Ident,
@@ -211,31 +257,67 @@ fn generate_transport(
     TokenStream::new()
 }
 
-fn generate_methods_plain<T: Service>(
+fn generate_methods<T: Service>(
     service: &T,
+    emit_package: bool,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_paths: &[String],
 ) -> TokenStream {
     let mut stream = TokenStream::new();
+    let package = if emit_package { service.package() } else { "" };
 
-    for method in service.methods() {
+    for (method, codec_path) in method_codec_paths(service, codec_paths) {
         let path = format!(
-            "/",
+            "/{}{}{}/{}",
+            package,
+            if package.is_empty() { "" } else { "." },
+            service.identifier(),
+            method.identifier()
         );
-        let _method_path = Lit::Str(LitStr::new(&path, Span::call_site()));
+        let method_path = Lit::Str(LitStr::new(&path, Span::call_site()));
         let ident = quote::format_ident!("{}", method.name());
         let server_trait = quote::format_ident!("{}", service.name());
 
-        let method_stream = generate_unary(
-            method,
-            proto_path,
-            compile_well_known_types,
-            ident,
-            server_trait,
-        );
+        let method_stream = match (method.client_streaming(), method.server_streaming()) {
+            (false, false) => generate_unary(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                ident,
+                server_trait,
+            ),
+            (false, true) => generate_server_streaming(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                ident,
+                server_trait,
+            ),
+            (true, false) => generate_client_streaming(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                ident,
+                server_trait,
+            ),
+            (true, true) => generate_streaming(
+                method,
+                proto_path,
+                compile_well_known_types,
+                codec_path,
+                ident,
+                server_trait,
+            ),
+        };
 
         let method = quote! {
-            #method_stream
+            Some(#method_path) => {
+                #method_stream
+            }
         };
         stream.extend(method);
     }
@@ -243,49 +325,117 @@ fn generate_methods_plain<T: Service>(
     stream
 }
 
-#[allow(dead_code)]
-fn generate_methods<T: Service>(
-    service: &T,
+fn generate_unary<T: Method>(
+    method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_path: &str,
+    method_ident: Ident,
+    server_trait: Ident,
 ) -> TokenStream {
-    let mut stream = TokenStream::new();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
 
-    for method in service.methods() {
-        let path = format!(
-            "/",
-        );
-        let method_path = Lit::Str(LitStr::new(&path, Span::call_site()));
-        let ident = quote::format_ident!("{}", method.name());
-        let server_trait = quote::format_ident!("{}", service.name());
+    let service_ident = quote::format_ident!("{}", method.identifier());
 
-        let method_stream = generate_unary(
-            method,
-            proto_path,
-            compile_well_known_types,
-            ident,
-            server_trait,
-        );
+    let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
 
-        let method = quote! {
-            #method_path => {
-                #method_stream
+    quote! {
+        #[allow(non_camel_case_types)]
+        struct #service_ident<T: #server_trait >(pub Arc<T>);
+
+        impl<T: #server_trait> lucat::server::UnaryService<#request> for #service_ident<T> {
+            type Response = #response;
+            type Future = BoxFuture<lucat::Response<Self::Response>, lucat::Status>;
+
+            fn call(&mut self, request: lucat::Request<#request>) -> Self::Future {
+                let inner = self.0.clone();
+                let fut = async move {
+                    (*inner).#method_ident(request).await
+                };
+                Box::pin(fut)
             }
+        }
+
+        let inner = self.inner.clone();
+        let max_decoding_message_size = self.max_decoding_message_size;
+        let max_encoding_message_size = self.max_encoding_message_size;
+        let fut = async move {
+            let inner = inner.0;
+            let method = #service_ident(inner);
+            let codec = #codec_name::default();
+
+            let mut grpc = lucat::server::Rpc::new(codec)
+                .apply_max_message_size_config(max_decoding_message_size, max_encoding_message_size);
+
+            let res = grpc.unary(method, req).await;
+            Ok(res)
         };
-        stream.extend(method);
+
+        Box::pin(fut)
     }
+}
 
-    stream
+fn generate_server_streaming<T: Method>(
+    method: &T,
+    proto_path: &str,
+    compile_well_known_types: bool,
+    codec_path: &str,
+    method_ident: Ident,
+    server_trait: Ident,
+) -> TokenStream {
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
+
+    let service_ident = quote::format_ident!("{}", method.identifier());
+    let response_stream = quote::format_ident!("{}Stream", method.identifier());
+
+    let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+
+    quote! {
+        #[allow(non_camel_case_types)]
+        struct #service_ident<T: #server_trait >(pub Arc<T>);
+
+        impl<T: #server_trait> lucat::server::ServerStreamingService<#request> for #service_ident<T> {
+            type Response = #response;
+            type ResponseStream = T::#response_stream;
+            type Future = BoxFuture<lucat::Response<Self::ResponseStream>, lucat::Status>;
+
+            fn call(&mut self, request: lucat::Request<#request>) -> Self::Future {
+                let inner = self.0.clone();
+                let fut = async move {
+                    (*inner).#method_ident(request).await
+                };
+                Box::pin(fut)
+            }
+        }
+
+        let inner = self.inner.clone();
+        let max_decoding_message_size = self.max_decoding_message_size;
+        let max_encoding_message_size = self.max_encoding_message_size;
+        let fut = async move {
+            let inner = inner.0;
+            let method = #service_ident(inner);
+            let codec = #codec_name::default();
+
+            let mut grpc = lucat::server::Rpc::new(codec)
+                .apply_max_message_size_config(max_decoding_message_size, max_encoding_message_size);
+
+            let res = grpc.server_streaming(method, req).await;
+            Ok(res)
+        };
+
+        Box::pin(fut)
+    }
 }
 
-fn generate_unary<T: Method>(
+fn generate_client_streaming<T: Method>(
     method: &T,
     proto_path: &str,
     compile_well_known_types: bool,
+    codec_path: &str,
     method_ident: Ident,
     server_trait: Ident,
 ) -> TokenStream {
-    let codec_name = syn::parse_str::<syn::Path>(T::CODEC_PATH).unwrap();
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
 
     let service_ident = quote::format_ident!("{}", method.identifier());
 
@@ -295,11 +445,11 @@ fn generate_unary<T: Method>(
         #[allow(non_camel_case_types)]
         struct #service_ident<T: #server_trait >(pub Arc<T>);
 
-        impl<T: #server_trait> lucat::server::UnaryService<#request> for #service_ident<T> {
+        impl<T: #server_trait> lucat::server::ClientStreamingService<#request> for #service_ident<T> {
             type Response = #response;
             type Future = BoxFuture<lucat::Response<Self::Response>, lucat::Status>;
 
-            fn call(&mut self, request: lucat::Request<#request>) -> Self::Future {
+            fn call(&mut self, request: lucat::Request<lucat::Streaming<#request>>) -> Self::Future {
                 let inner = self.0.clone();
                 let fut = async move {
                     (*inner).#method_ident(request).await
@@ -309,14 +459,69 @@ fn generate_unary<T: Method>(
         }
 
         let inner = self.inner.clone();
+        let max_decoding_message_size = self.max_decoding_message_size;
+        let max_encoding_message_size = self.max_encoding_message_size;
         let fut = async move {
             let inner = inner.0;
             let method = #service_ident(inner);
             let codec = #codec_name::default();
 
-            let mut grpc = lucat::server::Rpc::new(codec);
+            let mut grpc = lucat::server::Rpc::new(codec)
+                .apply_max_message_size_config(max_decoding_message_size, max_encoding_message_size);
 
-            let res = grpc.unary(method, req).await;
+            let res = grpc.client_streaming(method, req).await;
+            Ok(res)
+        };
+
+        Box::pin(fut)
+    }
+}
+
+fn generate_streaming<T: Method>(
+    method: &T,
+    proto_path: &str,
+    compile_well_known_types: bool,
+    codec_path: &str,
+    method_ident: Ident,
+    server_trait: Ident,
+) -> TokenStream {
+    let codec_name = syn::parse_str::<syn::Path>(codec_path).unwrap();
+
+    let service_ident = quote::format_ident!("{}", method.identifier());
+    let response_stream = quote::format_ident!("{}Stream", method.identifier());
+
+    let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
+
+    quote! {
+        #[allow(non_camel_case_types)]
+        struct #service_ident<T: #server_trait >(pub Arc<T>);
+
+        impl<T: #server_trait> lucat::server::StreamingService<#request> for #service_ident<T> {
+            type Response = #response;
+            type ResponseStream = T::#response_stream;
+            type Future = BoxFuture<lucat::Response<Self::ResponseStream>, lucat::Status>;
+
+            fn call(&mut self, request: lucat::Request<lucat::Streaming<#request>>) -> Self::Future {
+                let inner = self.0.clone();
+                let fut = async move {
+                    (*inner).#method_ident(request).await
+                };
+                Box::pin(fut)
+            }
+        }
+
+        let inner = self.inner.clone();
+        let max_decoding_message_size = self.max_decoding_message_size;
+        let max_encoding_message_size = self.max_encoding_message_size;
+        let fut = async move {
+            let inner = inner.0;
+            let method = #service_ident(inner);
+            let codec = #codec_name::default();
+
+            let mut grpc = lucat::server::Rpc::new(codec)
+                .apply_max_message_size_config(max_decoding_message_size, max_encoding_message_size);
+
+            let res = grpc.streaming(method, req).await;
             Ok(res)
         };
 
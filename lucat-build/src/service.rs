@@ -34,6 +34,29 @@ pub trait Method {
     ) -> (TokenStream, TokenStream);
 }
 
+// Pairs each method of `service` with its codec: the entry of `codec_paths`
+// at the same index, or `CODEC_PATH` of the method when no overrides are given.
+pub(crate) fn method_codec_paths<'a, T: Service>(
+    service: &'a T,
+    codec_paths: &'a [String],
+) -> impl Iterator<Item = (&'a T::Method, &'a str)> {
+    let methods = service.methods();
+    assert!(
+        codec_paths.is_empty() || codec_paths.len() == methods.len(),
+        "{} codec paths for the {} methods of {}",
+        codec_paths.len(),
+        methods.len(),
+        service.name()
+    );
+
+    methods.iter().enumerate().map(move |(i, method)| {
+        let codec_path = codec_paths
+            .get(i)
+            .map_or(<T::Method as Method>::CODEC_PATH, String::as_str);
+        (method, codec_path)
+    })
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Attributes {
     module: Vec<(String, String)>,
@@ -0,0 +1,142 @@
+use prost_types::field_descriptor_proto::Label;
+use std::fmt::Write;
+
+use lucat::dynamic::{
+    DescriptorPool, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor, MethodDescriptor,
+    ServiceDescriptor,
+};
+
+// Prints `symbol` the way it would be declared in a .proto file.
+pub fn describe(pool: &DescriptorPool, symbol: &str) -> Option<String> {
+    if let Some(service) = pool.get_service_by_name(symbol) {
+        return Some(format!("{} is a service:\n{}", symbol, service_decl(&service)));
+    }
+    if let Some(method) = find_method(pool, symbol) {
+        return Some(format!("{} is a method:\n{}\n", symbol, method_decl(&method)));
+    }
+    if let Some(message) = pool.get_message_by_name(symbol) {
+        return Some(format!("{} is a message:\n{}", symbol, message_decl(&message)));
+    }
+    if let Some(enumeration) = pool.get_enum_by_name(symbol) {
+        return Some(format!("{} is an enum:\n{}", symbol, enum_decl(&enumeration)));
+    }
+    None
+}
+
+// accepts `pkg.Service.Method` as well as the `pkg.Service/Method` form of paths
+pub fn find_method(pool: &DescriptorPool, symbol: &str) -> Option<MethodDescriptor> {
+    let symbol = symbol.trim_start_matches('/');
+    let split = symbol.rfind(['/', '.'])?;
+    let service = pool.get_service_by_name(&symbol[..split])?;
+    service.get_method_by_name(&symbol[split + 1..])
+}
+
+fn service_decl(service: &ServiceDescriptor) -> String {
+    let mut decl = format!("service {} {{\n", service.name());
+    for method in service.methods() {
+        let _ = writeln!(decl, "  {}", method_decl(&method));
+    }
+    decl.push_str("}\n");
+    decl
+}
+
+fn method_decl(method: &MethodDescriptor) -> String {
+    let name = |message: Option<MessageDescriptor>| match message {
+        Some(message) => format!(".{}", message.full_name()),
+        None => "?".to_string(),
+    };
+    let stream = |streaming: bool| if streaming { "stream " } else { "" };
+
+    format!(
+        "rpc {} ( {}{} ) returns ( {}{} );",
+        method.name(),
+        stream(method.is_client_streaming()),
+        name(method.input()),
+        stream(method.is_server_streaming()),
+        name(method.output()),
+    )
+}
+
+fn message_decl(message: &MessageDescriptor) -> String {
+    let short = message.full_name().rsplit('.').next().unwrap_or_default();
+    let mut decl = format!("message {} {{\n", short);
+    let mut oneof: Option<String> = None;
+
+    for field in message.fields() {
+        let field_oneof = field.oneof_name().map(str::to_string);
+        if field_oneof != oneof {
+            if oneof.is_some() {
+                decl.push_str("  }\n");
+            }
+            if let Some(name) = field_oneof.as_ref() {
+                let _ = writeln!(decl, "  oneof {} {{", name);
+            }
+            oneof = field_oneof;
+        }
+
+        let indent = if oneof.is_some() { "    " } else { "  " };
+        let _ = writeln!(decl, "{}{};", indent, field_decl(&field));
+    }
+    if oneof.is_some() {
+        decl.push_str("  }\n");
+    }
+
+    decl.push_str("}\n");
+    decl
+}
+
+fn field_decl(field: &FieldDescriptor) -> String {
+    let kind = field.kind();
+    let ty = if field.is_map() {
+        let entry = match &kind {
+            Kind::Message(entry) => entry,
+            _ => unreachable!(),
+        };
+        let key = entry.get_field(1).map(|f| type_name(&f.kind())).unwrap_or_default();
+        let value = entry.get_field(2).map(|f| type_name(&f.kind())).unwrap_or_default();
+        format!("map<{}, {}>", key, value)
+    } else {
+        let label = match field.field_descriptor_proto().label() {
+            Label::Repeated => "repeated ",
+            Label::Required => "required ",
+            Label::Optional if field.field_descriptor_proto().proto3_optional() => "optional ",
+            Label::Optional => "",
+        };
+        format!("{}{}", label, type_name(&kind))
+    };
+
+    format!("{} {} = {}", ty, field.name(), field.number())
+}
+
+fn type_name(kind: &Kind) -> String {
+    let name = match kind {
+        Kind::Double => "double",
+        Kind::Float => "float",
+        Kind::Int32 => "int32",
+        Kind::Int64 => "int64",
+        Kind::Uint32 => "uint32",
+        Kind::Uint64 => "uint64",
+        Kind::Sint32 => "sint32",
+        Kind::Sint64 => "sint64",
+        Kind::Fixed32 => "fixed32",
+        Kind::Fixed64 => "fixed64",
+        Kind::Sfixed32 => "sfixed32",
+        Kind::Sfixed64 => "sfixed64",
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::Message(message) => return format!(".{}", message.full_name()),
+        Kind::Enum(enumeration) => return format!(".{}", enumeration.full_name()),
+    };
+    name.to_string()
+}
+
+fn enum_decl(enumeration: &EnumDescriptor) -> String {
+    let short = enumeration.full_name().rsplit('.').next().unwrap_or_default();
+    let mut decl = format!("enum {} {{\n", short);
+    for value in enumeration.enum_descriptor_proto().value.iter() {
+        let _ = writeln!(decl, "  {} = {};", value.name(), value.number());
+    }
+    decl.push_str("}\n");
+    decl
+}
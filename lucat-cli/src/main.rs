@@ -0,0 +1,415 @@
+mod describe;
+mod source;
+
+use anyhow::{anyhow, bail, Result};
+use cynthia::runtime::stream;
+use http::uri::PathAndQuery;
+use lucat::client::Rpc;
+use lucat::codec::DynamicCodec;
+use lucat::dynamic::{self, DescriptorPool, DynamicMessage, MessageDescriptor};
+use lucat::metadata::{AsciiMetadataKey, BinaryMetadataKey, BinaryMetadataValue, MetadataMap};
+use lucat::status_details::{
+    self, BadRequest, DebugInfo, Detail, ErrorInfo, LocalizedMessage, QuotaFailure, ResourceInfo,
+    RetryInfo,
+};
+use lucat::{Request, Status};
+use prost_types::Any;
+use serde_json::Value as Json;
+use std::env;
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process;
+
+use crate::source::{Reflection, Source};
+
+const USAGE: &str = "\
+usage: lucat [flags] <address> list [service]
+       lucat [flags] <address> describe [symbol]
+       lucat [flags] <address> call <method>
+
+Service definitions are fetched through server reflection unless -proto or
+-protoset is given.
+
+flags:
+  -proto <file>         .proto file to read definitions from, may be repeated
+  -import-path <dir>    directory imports of -proto files are resolved in, may be repeated
+  -protoset <file>      compiled FileDescriptorSet to read definitions from, may be repeated
+  -d <json>             request body for call, several objects for client streaming;
+                        `@` reads it from stdin
+  -H <header>           `name: value` metadata sent with the call, may be repeated
+";
+
+enum Command {
+    List(Option<String>),
+    Describe(Option<String>),
+    Call(String),
+}
+
+struct Args {
+    protos: Vec<PathBuf>,
+    import_paths: Vec<PathBuf>,
+    protosets: Vec<PathBuf>,
+    data: Option<String>,
+    headers: Vec<(String, String)>,
+    address: String,
+    command: Command,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut protos = Vec::new();
+    let mut import_paths = Vec::new();
+    let mut protosets = Vec::new();
+    let mut data = None;
+    let mut headers = Vec::new();
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let flag = match arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+            Some(flag) if !flag.is_empty() => flag.to_string(),
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+
+        if flag == "h" || flag == "help" {
+            print!("{}", USAGE);
+            process::exit(0);
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow!("flag -{} needs a value", flag))?;
+        match flag.as_str() {
+            "proto" => protos.push(PathBuf::from(value)),
+            "import-path" => import_paths.push(PathBuf::from(value)),
+            "protoset" => protosets.push(PathBuf::from(value)),
+            "d" => data = Some(value),
+            "H" => headers.push(parse_header(&value)?),
+            _ => bail!("unknown flag -{}", flag),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let address = positional.next().ok_or_else(|| anyhow!("missing address"))?;
+    let command = match positional.next().as_deref() {
+        Some("list") => Command::List(positional.next()),
+        Some("describe") => Command::Describe(positional.next()),
+        Some("call") => Command::Call(positional.next().ok_or_else(|| anyhow!("missing method"))?),
+        Some(command) => bail!("unknown command {:?}", command),
+        None => bail!("missing command"),
+    };
+    if let Some(extra) = positional.next() {
+        bail!("unexpected argument {:?}", extra);
+    }
+
+    Ok(Args {
+        protos,
+        import_paths,
+        protosets,
+        data,
+        headers,
+        address,
+        command,
+    })
+}
+
+#[cynthia::main]
+async fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(args).await {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mut source = if !args.protos.is_empty() {
+        Source::Files(source::compile_protos(&args.protos, &args.import_paths)?)
+    } else if !args.protosets.is_empty() {
+        Source::Files(source::load_protoset(&args.protosets)?)
+    } else {
+        let endpoint = source::connect(&args.address).await?;
+        Source::Reflection(Box::new(Reflection::new(endpoint)))
+    };
+
+    match args.command {
+        Command::List(None) => {
+            let mut services = source.list_services().await?;
+            services.sort();
+            for service in services {
+                println!("{}", service);
+            }
+        }
+        Command::List(Some(name)) => {
+            let pool = source.pool_for(&name).await?;
+            let service = pool
+                .get_service_by_name(&name)
+                .ok_or_else(|| anyhow!("service {:?} not found", name))?;
+            for method in service.methods() {
+                println!("{}", method.full_name());
+            }
+        }
+        Command::Describe(None) => {
+            let mut services = source.list_services().await?;
+            services.sort();
+            for service in services {
+                let pool = source.pool_for(&service).await?;
+                if let Some(decl) = describe::describe(&pool, &service) {
+                    println!("{}", decl);
+                }
+            }
+        }
+        Command::Describe(Some(symbol)) => {
+            let pool = source.pool_for(&symbol).await?;
+            let decl = describe::describe(&pool, &symbol)
+                .ok_or_else(|| anyhow!("symbol {:?} not found", symbol))?;
+            print!("{}", decl);
+        }
+        Command::Call(name) => {
+            let data = match args.data.as_deref() {
+                Some("@") => {
+                    let mut data = String::new();
+                    io::stdin().read_to_string(&mut data)?;
+                    data
+                }
+                Some(data) => data.to_string(),
+                None => "{}".to_string(),
+            };
+            call(&mut source, &args.address, &name, &data, &args.headers).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn call(
+    source: &mut Source,
+    address: &str,
+    name: &str,
+    data: &str,
+    headers: &[(String, String)],
+) -> Result<()> {
+    let symbol = name.trim_start_matches('/').replace('/', ".");
+    let pool = source.pool_for(&symbol).await?;
+    let method = describe::find_method(&pool, &symbol)
+        .ok_or_else(|| anyhow!("method {:?} not found", name))?;
+    let input = method
+        .input()
+        .ok_or_else(|| anyhow!("request type of {} not found", symbol))?;
+    let codec = DynamicCodec::for_method(&method)
+        .ok_or_else(|| anyhow!("response type of {} not found", symbol))?;
+
+    let requests = parse_requests(input, data)?;
+    if !method.is_client_streaming() && requests.len() != 1 {
+        bail!("{} takes exactly one request message, got {}", symbol, requests.len());
+    }
+
+    // a call after reflection goes over the connection it already made
+    let endpoint = match source.endpoint() {
+        Some(endpoint) => endpoint,
+        None => source::connect(address).await?,
+    };
+    let mut rpc = Rpc::new(endpoint);
+
+    let path: PathAndQuery = method.path().parse()?;
+    let mut request = Request::new(stream::iter(requests));
+    for (name, value) in headers {
+        insert_header(request.metadata_mut(), name, value)?;
+    }
+    let response = match rpc.streaming(request, path, codec).await {
+        Ok(response) => response,
+        Err(status) => return Err(failed(&status, &pool)),
+    };
+
+    print_metadata("Response headers received:", response.metadata());
+
+    println!("\nResponse contents:");
+    let mut responses = response.into_inner();
+    loop {
+        match responses.message().await {
+            Ok(Some(message)) => {
+                println!("{}", serde_json::to_string_pretty(&dynamic::to_json(&message)?)?);
+            }
+            Ok(None) => break,
+            Err(status) => return Err(failed(&status, &pool)),
+        }
+    }
+
+    let trailers = responses.trailers().cloned().unwrap_or_default();
+    print_metadata("\nResponse trailers received:", &trailers);
+
+    Ok(())
+}
+
+fn print_metadata(title: &str, metadata: &MetadataMap) {
+    println!("{}", title);
+    for (name, value) in metadata.clone().into_headers().iter() {
+        println!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+    }
+}
+
+fn parse_header(header: &str) -> Result<(String, String)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| anyhow!("header {:?} is not of the form `name: value`", header))?;
+    Ok((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+}
+
+// `-bin` values are given as they are and base64 encoded on the wire.
+fn insert_header(metadata: &mut MetadataMap, name: &str, value: &str) -> Result<()> {
+    if name.ends_with("-bin") {
+        let key = BinaryMetadataKey::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow!("invalid header name {:?}", name))?;
+        metadata.append_bin(key, BinaryMetadataValue::from_bytes(value.as_bytes()));
+    } else {
+        let key = AsciiMetadataKey::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow!("invalid header name {:?}", name))?;
+        let value = value
+            .parse()
+            .map_err(|_| anyhow!("invalid value for header {:?}", name))?;
+        metadata.append(key, value);
+    }
+    Ok(())
+}
+
+fn parse_requests(descriptor: MessageDescriptor, data: &str) -> Result<Vec<DynamicMessage>> {
+    serde_json::Deserializer::from_str(data)
+        .into_iter::<Json>()
+        .map(|json| dynamic::from_json(descriptor.clone(), &json?))
+        .collect()
+}
+
+// The trailers of a failed call are printed like those of any other, its
+// status and details become the error the CLI exits with.
+fn failed(status: &Status, pool: &DescriptorPool) -> anyhow::Error {
+    print_metadata("\nResponse trailers received:", status.metadata());
+
+    let mut error = format!("\n  Code: {:?}\n  Message: {}", status.code(), status.message());
+    let details = status_details::extract(status).unwrap_or_default();
+    if !details.details().is_empty() {
+        error.push_str("\n  Details:");
+        for (i, detail) in details.details().iter().enumerate() {
+            error.push_str(&format!("\n  {}) {}", i + 1, format_detail(pool, detail)));
+        }
+    }
+    anyhow!(error)
+}
+
+// Details of types the pool knows are shown as JSON, the standard
+// google.rpc ones as they decode, anything else by its type alone.
+fn format_detail(pool: &DescriptorPool, detail: &Any) -> String {
+    let name = detail.type_url.rsplit('/').next().unwrap_or_default();
+    let json = pool
+        .get_message_by_name(name)
+        .and_then(|descriptor| DynamicMessage::decode(descriptor, &detail.value[..]).ok())
+        .and_then(|message| dynamic::to_json(&message).ok());
+    if let Some(json) = json {
+        return format!("{}: {}", detail.type_url, json);
+    }
+
+    debug_detail::<ErrorInfo>(detail)
+        .or_else(|| debug_detail::<BadRequest>(detail))
+        .or_else(|| debug_detail::<QuotaFailure>(detail))
+        .or_else(|| debug_detail::<RetryInfo>(detail))
+        .or_else(|| debug_detail::<ResourceInfo>(detail))
+        .or_else(|| debug_detail::<DebugInfo>(detail))
+        .or_else(|| debug_detail::<LocalizedMessage>(detail))
+        .map(|decoded| format!("{}: {}", detail.type_url, decoded))
+        .unwrap_or_else(|| format!("{} ({} bytes)", detail.type_url, detail.value.len()))
+}
+
+fn debug_detail<D: Detail + Debug>(detail: &Any) -> Option<String> {
+    D::from_any(detail).ok().flatten().map(|decoded| format!("{:?}", decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_err(args: &[&str]) -> String {
+        match parse(args) {
+            Ok(_) => panic!("{:?} parsed", args),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let args = parse(&["localhost:50051", "list"]).unwrap();
+        assert_eq!(args.address, "localhost:50051");
+        assert!(matches!(args.command, Command::List(None)));
+
+        let args = parse(&["localhost:50051", "describe", "echo.Echo"]).unwrap();
+        assert!(matches!(args.command, Command::Describe(Some(symbol)) if symbol == "echo.Echo"));
+
+        let args = parse(&["localhost:50051", "call", "echo.Echo/SayEcho"]).unwrap();
+        assert!(matches!(args.command, Command::Call(method) if method == "echo.Echo/SayEcho"));
+    }
+
+    #[test]
+    fn flags_are_parsed_anywhere() {
+        let args = parse(&[
+            "-proto",
+            "a.proto",
+            "localhost:50051",
+            "--proto",
+            "b.proto",
+            "-import-path",
+            "protos",
+            "call",
+            "-d",
+            r#"{"data": "aGk="}"#,
+            "echo.Echo/SayEcho",
+            "-H",
+            "X-Trace:  abc ",
+        ])
+        .unwrap();
+        assert_eq!(args.protos, vec![PathBuf::from("a.proto"), PathBuf::from("b.proto")]);
+        assert_eq!(args.import_paths, vec![PathBuf::from("protos")]);
+        assert!(args.protosets.is_empty());
+        assert_eq!(args.data.as_deref(), Some(r#"{"data": "aGk="}"#));
+        assert_eq!(args.headers, vec![("x-trace".to_string(), "abc".to_string())]);
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert_eq!(parse_err(&[]), "missing address");
+        assert_eq!(parse_err(&["localhost:50051"]), "missing command");
+        assert_eq!(parse_err(&["localhost:50051", "call"]), "missing method");
+        assert_eq!(parse_err(&["localhost:50051", "ping"]), "unknown command \"ping\"");
+        assert_eq!(
+            parse_err(&["localhost:50051", "list", "a", "b"]),
+            "unexpected argument \"b\""
+        );
+        assert_eq!(parse_err(&["localhost:50051", "list", "-d"]), "flag -d needs a value");
+        assert_eq!(parse_err(&["-x", "1", "localhost:50051", "list"]), "unknown flag -x");
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        assert_eq!(
+            parse_err(&["-H", "x-trace", "localhost:50051", "list"]),
+            "header \"x-trace\" is not of the form `name: value`"
+        );
+
+        let mut metadata = MetadataMap::new();
+        assert!(insert_header(&mut metadata, "bad header", "1").is_err());
+        assert!(insert_header(&mut metadata, "x-trace", "line\nbreak").is_err());
+        assert!(insert_header(&mut metadata, "x-trace", "abc").is_ok());
+        assert!(insert_header(&mut metadata, "x-trace-bin", "\u{0}raw").is_ok());
+        assert_eq!(metadata.get("x-trace").unwrap(), "abc");
+    }
+}
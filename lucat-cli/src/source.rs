@@ -0,0 +1,191 @@
+use anyhow::{anyhow, bail, Context, Result};
+use cynthia::runtime::stream;
+use lucat::reflection::pb::server_reflection_client::ServerReflectionClient;
+use lucat::reflection::pb::server_reflection_request::MessageRequest;
+use lucat::reflection::pb::server_reflection_response::MessageResponse;
+use lucat::reflection::pb::ServerReflectionRequest;
+use lucat::transport::Endpoint;
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use lucat::dynamic::DescriptorPool;
+
+// Where service and message definitions come from: the server itself, or
+// local files when the server does not expose reflection.
+pub enum Source {
+    Reflection(Box<Reflection>),
+    Files(DescriptorPool),
+}
+
+impl Source {
+    pub async fn list_services(&mut self) -> Result<Vec<String>> {
+        match self {
+            Source::Reflection(reflection) => reflection.list_services().await,
+            Source::Files(pool) => Ok(pool
+                .services()
+                .map(|service| service.full_name().to_string())
+                .collect()),
+        }
+    }
+
+    // a pool that is guaranteed to define `symbol` if the source knows it
+    pub async fn pool_for(&mut self, symbol: &str) -> Result<DescriptorPool> {
+        match self {
+            Source::Reflection(reflection) => reflection.file_containing_symbol(symbol).await,
+            Source::Files(pool) => Ok(pool.clone()),
+        }
+    }
+
+    // the connection reflection already made, if any
+    pub fn endpoint(&self) -> Option<Endpoint> {
+        match self {
+            Source::Reflection(reflection) => Some(reflection.endpoint.clone()),
+            Source::Files(_) => None,
+        }
+    }
+}
+
+pub async fn connect(address: &str) -> Result<Endpoint> {
+    Endpoint::connect(address.to_string())
+        .await
+        .map_err(|e| anyhow!("failed to connect to {}: {}", address, e))
+}
+
+pub struct Reflection {
+    endpoint: Endpoint,
+    client: ServerReflectionClient<Endpoint>,
+}
+
+impl Reflection {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Reflection {
+            client: ServerReflectionClient::new(endpoint.clone()),
+            endpoint,
+        }
+    }
+
+    async fn request(&mut self, request: MessageRequest) -> Result<MessageResponse> {
+        let request = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(request),
+        };
+
+        let mut responses = self
+            .client
+            .server_reflection_info(stream::once(request))
+            .await
+            .context("server does not support the reflection API")?
+            .into_inner();
+
+        let response = responses
+            .message()
+            .await?
+            .and_then(|response| response.message_response)
+            .ok_or_else(|| anyhow!("empty reflection response"))?;
+
+        match response {
+            MessageResponse::ErrorResponse(error) => bail!("{}", error.error_message),
+            response => Ok(response),
+        }
+    }
+
+    async fn list_services(&mut self) -> Result<Vec<String>> {
+        match self.request(MessageRequest::ListServices(String::new())).await? {
+            MessageResponse::ListServicesResponse(list) => {
+                Ok(list.service.into_iter().map(|service| service.name).collect())
+            }
+            _ => bail!("unexpected reflection response"),
+        }
+    }
+
+    async fn file_containing_symbol(&mut self, symbol: &str) -> Result<DescriptorPool> {
+        let request = MessageRequest::FileContainingSymbol(symbol.to_string());
+        let mut files = self.files(request).await?;
+
+        // not every server sends the dependencies along, ask for the missing ones
+        let mut known: HashSet<String> = files.iter().map(|file| file.name().to_string()).collect();
+        let mut index = 0;
+        while index < files.len() {
+            let missing: Vec<String> = files[index]
+                .dependency
+                .iter()
+                .filter(|dependency| !known.contains(dependency.as_str()))
+                .cloned()
+                .collect();
+
+            for dependency in missing {
+                for file in self.files(MessageRequest::FileByFilename(dependency)).await? {
+                    if known.insert(file.name().to_string()) {
+                        files.push(file);
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        Ok(DescriptorPool::new(FileDescriptorSet { file: files }))
+    }
+
+    async fn files(&mut self, request: MessageRequest) -> Result<Vec<FileDescriptorProto>> {
+        match self.request(request).await? {
+            MessageResponse::FileDescriptorResponse(response) => response
+                .file_descriptor_proto
+                .iter()
+                .map(|encoded| Ok(FileDescriptorProto::decode(&encoded[..])?))
+                .collect(),
+            _ => bail!("unexpected reflection response"),
+        }
+    }
+}
+
+pub fn load_protoset(paths: &[PathBuf]) -> Result<DescriptorPool> {
+    let mut set = FileDescriptorSet::default();
+    for path in paths {
+        let encoded = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let decoded = FileDescriptorSet::decode(&encoded[..])
+            .with_context(|| format!("{} is not a file descriptor set", path.display()))?;
+        set.file.extend(decoded.file);
+    }
+    Ok(DescriptorPool::new(set))
+}
+
+// Compiles the files with the protoc bundled by prost-build.
+pub fn compile_protos(protos: &[PathBuf], import_paths: &[PathBuf]) -> Result<DescriptorPool> {
+    let out = std::env::temp_dir().join(format!("lucat-{}.protoset", std::process::id()));
+
+    let mut cmd = Command::new(prost_build::protoc());
+    cmd.arg("--include_imports")
+        .arg(format!("--descriptor_set_out={}", out.display()));
+    if import_paths.is_empty() {
+        // without import paths every file is resolved next to itself
+        let mut dirs: Vec<&Path> = protos
+            .iter()
+            .map(|proto| match proto.parent() {
+                Some(dir) if dir != Path::new("") => dir,
+                _ => Path::new("."),
+            })
+            .collect();
+        dirs.dedup();
+        for dir in dirs {
+            cmd.arg(format!("-I{}", dir.display()));
+        }
+    }
+    for path in import_paths {
+        cmd.arg(format!("-I{}", path.display()));
+    }
+    cmd.arg(format!("-I{}", prost_build::protoc_include().display()));
+    cmd.args(protos);
+
+    let output = cmd.output().context("failed to run protoc")?;
+    if !output.status.success() {
+        bail!("protoc failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let pool = load_protoset(std::slice::from_ref(&out));
+    let _ = fs::remove_file(&out);
+    pool
+}
@@ -0,0 +1,26 @@
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    lucat_build::configure()
+        .build_client(true)
+        .build_server(true)
+        .compile(&["proto/health.proto"], &["proto"])
+        .unwrap();
+
+    lucat_build::configure()
+        .build_client(true)
+        .build_server(true)
+        .file_descriptor_set_path(out_dir.join("reflection_descriptor.bin"))
+        .compile(&["proto/reflection.proto"], &["proto"])
+        .unwrap();
+
+    lucat_build::configure()
+        .compile(
+            &["proto/google/rpc/status.proto", "proto/google/rpc/error_details.proto"],
+            &["proto"],
+        )
+        .unwrap();
+}
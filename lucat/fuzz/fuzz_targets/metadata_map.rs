@@ -0,0 +1,34 @@
+#![no_main]
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+use lucat::metadata::{AsciiMetadataKey, BinaryMetadataKey, KeyAndValueRef, MetadataMap};
+
+// The input is split on NUL bytes into alternating header names and values.
+fuzz_target!(|data: &[u8]| {
+    let mut parts = data.split(|b| *b == 0);
+
+    let mut headers = HeaderMap::new();
+    while let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+        let _ = AsciiMetadataKey::from_bytes(name);
+        let _ = BinaryMetadataKey::from_bytes(name);
+        if let (Ok(name), Ok(value)) =
+            (HeaderName::from_bytes(name), HeaderValue::from_bytes(value))
+        {
+            headers.append(name, value);
+        }
+    }
+
+    let metadata = MetadataMap::from_headers(headers);
+    for entry in metadata.iter() {
+        match entry {
+            KeyAndValueRef::Ascii(_, value) => {
+                let _ = value.to_str();
+                let _ = value.to_bytes();
+            }
+            KeyAndValueRef::Binary(_, value) => {
+                let _ = value.to_bytes();
+            }
+        }
+    }
+    let _ = metadata.into_headers();
+});
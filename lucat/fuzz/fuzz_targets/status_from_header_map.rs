@@ -0,0 +1,23 @@
+#![no_main]
+use http::header::{HeaderMap, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+use lucat::status_details;
+use lucat::Status;
+
+// The input is split on NUL bytes into grpc-status, grpc-message and
+// grpc-status-details-bin, the trailers a peer controls.
+fuzz_target!(|data: &[u8]| {
+    let names = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in names.iter().zip(data.split(|b| *b == 0)) {
+        if let Ok(value) = HeaderValue::from_bytes(value) {
+            headers.insert(*name, value);
+        }
+    }
+
+    if let Some(status) = Status::from_header_map(&headers) {
+        let _ = status.to_header_map();
+        let _ = status_details::extract(&status);
+    }
+});
@@ -0,0 +1,130 @@
+use bytes::{Buf, BytesMut};
+use cynthia::runtime::future;
+use cynthia::runtime::stream::Stream;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::encode::HEADER_SIZE;
+use super::error::Error;
+use super::{Decoder, DEFAULT_MAX_DECODING_MESSAGE_SIZE};
+use crate::common::Body;
+use crate::metadata::MetadataMap;
+use crate::Status;
+
+pub struct Streaming<T> {
+    decoder: Box<dyn Decoder<Item = T, Error = Status> + Send + 'static>,
+    body: Body,
+    buf: BytesMut,
+    max_message_size: usize,
+    done: bool,
+}
+
+impl<T> Streaming<T> {
+    pub fn new<D>(body: Body, decoder: D) -> Self
+    where
+        D: Decoder<Item = T, Error = Status> + Send + 'static,
+    {
+        Streaming {
+            decoder: Box::new(decoder),
+            body,
+            buf: BytesMut::new(),
+            max_message_size: DEFAULT_MAX_DECODING_MESSAGE_SIZE,
+            done: false,
+        }
+    }
+
+    pub(crate) fn max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = max;
+        self
+    }
+
+    pub async fn message(&mut self) -> Result<Option<T>, Status> {
+        match future::poll_fn(|cx| self.poll_message(cx)).await {
+            Some(result) => result.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // The trailing metadata of the call, once all of its messages were read.
+    pub fn trailers(&self) -> Option<&MetadataMap> {
+        self.body.trailers()
+    }
+
+    fn decode_frame(&mut self) -> Result<Option<T>, Status> {
+        if self.buf.len() < HEADER_SIZE {
+            return Ok(None);
+        }
+
+        if self.buf[0] != 0 {
+            return Err(Error::Compressed.into());
+        }
+
+        // the length comes from the peer, so it is checked before anything is
+        // buffered for it, and the buffer only grows as the message arrives
+        let len = u32::from_be_bytes([self.buf[1], self.buf[2], self.buf[3], self.buf[4]]) as usize;
+        if len > self.max_message_size {
+            return Err(Error::TooLarge {
+                len,
+                max: self.max_message_size,
+            }
+            .into());
+        }
+        if self.buf.len() < HEADER_SIZE + len {
+            return Ok(None);
+        }
+
+        self.buf.advance(HEADER_SIZE);
+        let message = self.buf.split_to(len).freeze();
+        match self.decoder.decode(message)? {
+            Some(message) => Ok(Some(message)),
+            None => Err(Status::internal("failed to decode message")),
+        }
+    }
+
+    fn poll_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, Status>>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            match self.decode_frame() {
+                Ok(Some(message)) => return Poll::Ready(Some(Ok(message))),
+                Ok(None) => {}
+                Err(status) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+            }
+
+            match self.body.poll_data(cx) {
+                Poll::Ready(Some(Ok(data))) => self.buf.extend_from_slice(&data),
+                Poll::Ready(Some(Err(status))) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+                Poll::Ready(None) => {
+                    self.done = true;
+                    if !self.buf.is_empty() {
+                        return Poll::Ready(Some(Err(Error::Truncated.into())));
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> Stream for Streaming<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_message(cx)
+    }
+}
+
+impl<T> fmt::Debug for Streaming<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Streaming").finish()
+    }
+}
@@ -0,0 +1,76 @@
+use bytes::Bytes;
+use super::{Codec, Decoder, Encoder};
+use crate::dynamic::{DynamicMessage, MessageDescriptor, MethodDescriptor};
+use crate::{Code, Status};
+
+// Encodes and decodes messages of types that are only known at runtime, so
+// a method can be called from nothing but its descriptor.
+#[derive(Debug, Clone)]
+pub struct DynamicCodec {
+    request: MessageDescriptor,
+    response: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    pub fn new(request: MessageDescriptor, response: MessageDescriptor) -> Self {
+        DynamicCodec { request, response }
+    }
+
+    // the codec a client calling `method` needs, `None` if one of its
+    // message types is missing from the pool
+    pub fn for_method(method: &MethodDescriptor) -> Option<Self> {
+        Some(DynamicCodec::new(method.input()?, method.output()?))
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder(self.request.clone())
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.response.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicEncoder(MessageDescriptor);
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item) -> Result<Bytes, Self::Error> {
+        if item.descriptor() != &self.0 {
+            return Err(Status::new(
+                Code::Internal,
+                format!(
+                    "expected a {} message, got {}",
+                    self.0.full_name(),
+                    item.descriptor().full_name()
+                ),
+            ));
+        }
+        Ok(item.encode_to_bytes())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, buf: Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), buf)
+            .map(Some)
+            .map_err(|error| Status::new(Code::Internal, error.to_string()))
+    }
+}
@@ -0,0 +1,46 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use cynthia::runtime::stream::{Stream, StreamExt};
+
+use super::error::{self, Error};
+use super::Encoder;
+use crate::Status;
+
+pub(crate) const HEADER_SIZE: usize = 5;
+
+// Prefixes a message with the gRPC frame header: an uncompressed flag
+// followed by the big-endian message length.
+pub fn encode_frame(message: Bytes) -> error::Result<Bytes> {
+    encode_limited_frame(message, usize::MAX)
+}
+
+// `max` never goes beyond what the length in a frame header can hold.
+pub(crate) fn encode_limited_frame(message: Bytes, max: usize) -> error::Result<Bytes> {
+    let max = max.min(u32::MAX as usize);
+    if message.len() > max {
+        return Err(Error::TooLarge {
+            len: message.len(),
+            max,
+        });
+    }
+
+    let mut buf = BytesMut::with_capacity(HEADER_SIZE + message.len());
+    buf.put_u8(0);
+    buf.put_u32(message.len() as u32);
+    buf.extend_from_slice(&message);
+    Ok(buf.freeze())
+}
+
+pub(crate) fn encode<E, S>(
+    mut encoder: E,
+    source: S,
+    max: usize,
+) -> impl Stream<Item = Result<Bytes, Status>>
+where
+    E: Encoder<Error = Status>,
+    S: Stream<Item = Result<E::Item, Status>>,
+{
+    source.map(move |item| {
+        item.and_then(|message| encoder.encode(message))
+            .and_then(|message| encode_limited_frame(message, max).map_err(Status::from))
+    })
+}
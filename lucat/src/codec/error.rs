@@ -1,55 +1,77 @@
-use std::result;
-
-use failure;
-use prost;
-
-pub type Result<A, E> = result::Result<A, Error<E>>;
-
-#[derive(Clone, Debug, Eq, failure::Fail, PartialEq)]
-pub enum Error<E>
-where
-    E: failure::Fail,
-{
-    #[fail(display = "Execution error: {}", error)]
-    Execution {
-        #[cause]
-        error: E,
-    },
-    #[fail(display = "Decode error: {}", error)]
-    Decode {
-        #[cause]
-        error: prost::DecodeError,
-    },
-    #[fail(display = "Encode error: {}", error)]
-    Encode {
-        #[cause]
-        error: prost::EncodeError,
-    },
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::{Code, Status};
+
+pub type Result<A> = std::result::Result<A, Error>;
+
+// What can go wrong turning messages into gRPC frames and back.
+#[derive(Debug)]
+pub enum Error {
+    // the compressed flag is set, and compression is not supported
+    Compressed,
+    // a message does not fit in the length of a frame header
+    TooLarge { len: usize, max: usize },
+    // the stream ended in the middle of a frame
+    Truncated,
+    Decode(prost::DecodeError),
+    Encode(prost::EncodeError),
+}
+
+impl Error {
+    pub fn code(&self) -> Code {
+        match self {
+            Error::Compressed => Code::Unimplemented,
+            Error::TooLarge { .. } => Code::ResourceExhausted,
+            Error::Truncated | Error::Decode(_) | Error::Encode(_) => Code::Internal,
+        }
+    }
 }
 
-impl<E> Error<E>
-where
-    E: failure::Fail,
-{
-    pub fn execution(error: E) -> Self {
-        Error::Execution { error }
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Compressed => {
+                f.write_str("message is compressed, but compression is not supported")
+            }
+            Error::TooLarge { len, max } => {
+                write!(
+                    f,
+                    "message of {} bytes is larger than the limit of {} bytes",
+                    len, max
+                )
+            }
+            Error::Truncated => f.write_str("stream ended in the middle of a message"),
+            Error::Decode(error) => write!(f, "failed to decode message: {}", error),
+            Error::Encode(error) => write!(f, "failed to encode message: {}", error),
+        }
     }
 }
 
-impl<E> From<prost::DecodeError> for Error<E>
-where
-    E: failure::Fail,
-{
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Decode(error) => Some(error),
+            Error::Encode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<prost::DecodeError> for Error {
     fn from(error: prost::DecodeError) -> Self {
-        Error::Decode { error }
+        Error::Decode(error)
     }
 }
 
-impl<E> From<prost::EncodeError> for Error<E>
-where
-    E: failure::Fail,
-{
+impl From<prost::EncodeError> for Error {
     fn from(error: prost::EncodeError) -> Self {
-        Error::Encode { error }
+        Error::Encode(error)
+    }
+}
+
+impl From<Error> for Status {
+    fn from(error: Error) -> Self {
+        Status::new(error.code(), error.to_string())
     }
 }
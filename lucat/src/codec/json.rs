@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+use http::HeaderValue;
+use super::{Codec, Decoder, Encoder};
+use crate::dynamic::{self, DynamicMessage, ReflectMessage};
+use crate::{Code, Status};
+
+// Encodes messages with the canonical proto3 JSON mapping instead of the
+// binary wire format, going through their descriptors, which lucat-build
+// generates for the services using this codec. Servers still answer calls
+// made with `application/grpc` or `application/grpc+proto` in protobuf.
+#[derive(Debug, Clone)]
+pub struct JsonCodec<T, U> {
+    format: Format,
+    _pd: PhantomData<(T, U)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Proto,
+}
+
+impl<T, U> Default for JsonCodec<T, U> {
+    fn default() -> Self {
+        Self {
+            format: Format::Json,
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<T, U> Codec for JsonCodec<T, U>
+where
+    T: ReflectMessage + Send + Sync + 'static,
+    U: ReflectMessage + Send + Sync + 'static,
+{
+    const CONTENT_TYPE: &'static str = "application/grpc+json";
+
+    type Encode = T;
+    type Decode = U;
+
+    type Encoder = JsonEncoder<T>;
+    type Decoder = JsonDecoder<U>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        JsonEncoder(self.format, PhantomData)
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        JsonDecoder(self.format, PhantomData)
+    }
+
+    fn negotiate(&mut self, content_type: &HeaderValue) -> Result<(), Status> {
+        // parameters like `; charset=utf-8` don't change the format
+        let media_type = content_type.as_bytes().split(|b| *b == b';').next().unwrap_or_default();
+        self.format = match media_type {
+            b"application/grpc+json" => Format::Json,
+            b"application/grpc" | b"application/grpc+proto" => Format::Proto,
+            _ => {
+                return Err(Status::new(
+                    Code::Unimplemented,
+                    format!(
+                        "unsupported content-type {:?}",
+                        String::from_utf8_lossy(content_type.as_bytes())
+                    ),
+                ))
+            }
+        };
+        Ok(())
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        match self.format {
+            Format::Json => HeaderValue::from_static(Self::CONTENT_TYPE),
+            Format::Proto => HeaderValue::from_static("application/grpc"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonEncoder<T>(Format, PhantomData<T>);
+
+impl<T: ReflectMessage> Encoder for JsonEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item) -> Result<bytes::Bytes, Self::Error> {
+        let encoded = bytes::Bytes::from(item.encode_to_vec());
+        if self.0 == Format::Proto {
+            return Ok(encoded);
+        }
+
+        let message = DynamicMessage::decode(T::descriptor(), encoded)
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        dynamic::to_json(&message)
+            .and_then(|json| Ok(serde_json::to_vec(&json)?))
+            .map(bytes::Bytes::from)
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonDecoder<U>(Format, PhantomData<U>);
+
+impl<U: ReflectMessage> Decoder for JsonDecoder<U> {
+    type Item = U;
+    type Error = Status;
+
+    fn decode(&mut self, buf: bytes::Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        let encoded = match self.0 {
+            Format::Proto => buf,
+            Format::Json => serde_json::from_slice(&buf)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| dynamic::from_json(U::descriptor(), &json))
+                .map(|message| message.encode_to_bytes())
+                .map_err(|e| Status::new(Code::Internal, e.to_string()))?,
+        };
+
+        let item = U::decode(encoded).map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        Ok(Some(item))
+    }
+}
@@ -1,17 +1,33 @@
 pub mod buffer;
 pub mod prost;
+pub mod json;
+mod dynamic;
+mod raw;
+mod decode;
+mod encode;
 
 pub use self::buffer::{DecodeBuf, EncodeBuf};
+pub use self::decode::Streaming;
+pub use self::encode::encode_frame;
+pub(crate) use self::encode::{encode, encode_limited_frame};
 pub use crate::common::{self, Body, Request, Response};
 
 pub mod error;
 
 pub use crate::codec::prost::ProstCodec;
+pub use crate::codec::json::JsonCodec;
+pub use self::dynamic::{DynamicCodec, DynamicDecoder, DynamicEncoder};
+pub use self::raw::{BytesCodec, BytesDecoder, BytesEncoder};
 use crate::Status;
 
 use std::io;
 
-pub trait Codec: Default {
+pub const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+pub const DEFAULT_MAX_ENCODING_MESSAGE_SIZE: usize = usize::MAX;
+
+pub trait Codec {
+    const CONTENT_TYPE: &'static str = "application/grpc";
+
     type Encode: Send + 'static;
     type Decode: Send + 'static;
 
@@ -20,6 +36,68 @@ pub trait Codec: Default {
 
     fn encoder(&mut self) -> Self::Encoder;
     fn decoder(&mut self) -> Self::Decoder;
+
+    // Servers hand the codec the content-type a call arrived with before
+    // decoding it. A codec that speaks several formats switches to the one
+    // asked for, an error refuses the call.
+    fn negotiate(&mut self, _content_type: &http::HeaderValue) -> Result<(), Status> {
+        Ok(())
+    }
+
+    fn content_type(&self) -> http::HeaderValue {
+        http::HeaderValue::from_static(Self::CONTENT_TYPE)
+    }
+}
+
+// The content-type a call is made with, carried from the codec to the
+// transport in the request and response extensions.
+#[derive(Debug, Clone)]
+pub(crate) struct ContentType(pub(crate) http::HeaderValue);
+
+impl ContentType {
+    pub(crate) fn of<C: Codec>() -> Self {
+        ContentType(http::HeaderValue::from_static(C::CONTENT_TYPE))
+    }
+}
+
+// The message size limits a server or an endpoint applies to the calls it
+// carries, handed to them in the request and response extensions. Limits set
+// on a generated client or server take precedence.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MessageSizes {
+    pub(crate) max_decoding: Option<usize>,
+    pub(crate) max_encoding: Option<usize>,
+}
+
+impl MessageSizes {
+    pub(crate) fn max_decoding(own: Option<usize>, extensions: &http::Extensions) -> usize {
+        own.or_else(|| extensions.get::<MessageSizes>().and_then(|sizes| sizes.max_decoding))
+            .unwrap_or(DEFAULT_MAX_DECODING_MESSAGE_SIZE)
+    }
+
+    pub(crate) fn max_encoding(own: Option<usize>, extensions: &http::Extensions) -> usize {
+        own.or_else(|| extensions.get::<MessageSizes>().and_then(|sizes| sizes.max_encoding))
+            .unwrap_or(DEFAULT_MAX_ENCODING_MESSAGE_SIZE)
+    }
+}
+
+impl ContentType {
+    // `application/grpc`, or `application/grpc+<subtype>` like
+    // `application/grpc+proto`, with optional parameters after a `;`
+    pub(crate) fn is_grpc(content_type: &[u8]) -> bool {
+        let media_type = content_type.split(|b| *b == b';').next().unwrap_or_default();
+        match media_type.strip_prefix(b"application/grpc") {
+            Some(b"") => true,
+            Some(subtype) => subtype.len() > 1 && subtype[0] == b'+',
+            None => false,
+        }
+    }
+}
+
+impl Default for ContentType {
+    fn default() -> Self {
+        ContentType(http::HeaderValue::from_static("application/grpc"))
+    }
 }
 
 pub trait Encoder {
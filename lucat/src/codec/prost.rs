@@ -1,8 +1,8 @@
 use std::marker::PhantomData;
 use prost::Message;
 use super::{Codec, Decoder, Encoder};
-use crate::{Code, Status};
-use super::error;
+use crate::Status;
+use super::error::{self, Error};
 
 #[derive(Debug, Clone)]
 pub struct ProstCodec<T, U> {
@@ -45,7 +45,7 @@ impl<T: Message> Encoder for ProstEncoder<T> {
     fn encode(&mut self, item: Self::Item) -> Result<bytes::Bytes, Self::Error> {
         let len = Message::encoded_len(&item);
         let mut buf = ::bytes::BytesMut::with_capacity(len);
-        item.encode(&mut buf).expect("Message only errors if not enough space");
+        item.encode(&mut buf).map_err(Error::from)?;
         Ok(buf.freeze())
     }
 }
@@ -60,17 +60,13 @@ impl<U: Message + Default> Decoder for ProstDecoder<U> {
     fn decode(&mut self, buf: bytes::Bytes) -> Result<Option<Self::Item>, Self::Error> {
         let item = Message::decode(buf)
                     .map(Option::Some)
-                    .map_err(from_decode_error)?;
+                    .map_err(Error::from)?;
 
         Ok(item)
     }
 }
 
-fn from_decode_error(error: prost::DecodeError) -> crate::Status {
-    Status::new(Code::Internal, error.to_string())
-}
-
-pub fn decode<M>(buf: bytes::Bytes) -> Result<M, prost::DecodeError>
+pub fn decode<M>(buf: bytes::Bytes) -> error::Result<M>
 where
     M: prost::Message + Default,
 {
@@ -78,7 +74,7 @@ where
     Ok(message)
 }
 
-pub fn encode<M>(message: M) -> error::Result<bytes::Bytes, prost::DecodeError>
+pub fn encode<M>(message: M) -> error::Result<bytes::Bytes>
 where
     M: prost::Message,
 {
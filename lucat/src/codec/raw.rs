@@ -0,0 +1,48 @@
+use bytes::Bytes;
+use super::{Codec, Decoder, Encoder};
+use crate::Status;
+
+// Passes message payloads through untouched, for proxies and routers that
+// forward calls without knowing their message types.
+#[derive(Debug, Clone, Default)]
+pub struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+
+    type Encoder = BytesEncoder;
+    type Decoder = BytesDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesDecoder
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BytesEncoder;
+
+impl Encoder for BytesEncoder {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item) -> Result<Bytes, Self::Error> {
+        Ok(item)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BytesDecoder;
+
+impl Decoder for BytesDecoder {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, buf: Bytes) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(buf))
+    }
+}
@@ -1,45 +1,169 @@
 use nephele::proto::h2::{RecvStream};
 use bytes::{Bytes};
+use cynthia::runtime::future;
+use cynthia::runtime::stream::{self, Stream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::common::{Code, Status};
+use crate::metadata::MetadataMap;
+use crate::transport::ping::InFlight;
 
 pub struct Body {
-    pub data: Option<Bytes>,
+    kind: BodyType,
+    // the metadata of the trailers of a successful call, once they arrived
+    trailers: Option<MetadataMap>,
+    // keeps the call counted on its connection for as long as the body lives
+    in_flight: Option<InFlight>,
 }
 
 pub enum BodyType {
     Once(Option<Bytes>),
     H2 {
         recv: RecvStream,
+        data_done: bool,
+        // responses must end in a grpc-status, requests just end
+        status_required: bool,
     },
+    Stream(stream::Boxed<Result<Bytes, Status>>),
 }
 
 impl Body {
-    /*
     pub fn empty() -> Body {
-        Body::new(BodyType::Once(None))
+        Body::new(None)
     }
-    */
 
     pub fn new(data: Option<Bytes>) -> Body {
-        Body { data }
+        Body {
+            kind: BodyType::Once(data),
+            trailers: None,
+            in_flight: None,
+        }
+    }
+
+    pub fn h2(recv: RecvStream) -> Self {
+        Body {
+            kind: BodyType::H2 {
+                recv,
+                data_done: false,
+                status_required: false,
+            },
+            trailers: None,
+            in_flight: None,
+        }
+    }
+
+    pub fn stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, Status>> + Send + 'static,
+    {
+        Body {
+            kind: BodyType::Stream(Box::pin(stream)),
+            trailers: None,
+            in_flight: None,
+        }
+    }
+
+    // A body that fails before producing any data, sent as a trailers-only
+    // response by the server.
+    pub fn error(status: Status) -> Self {
+        Body::stream(stream::once(Err(status)))
+    }
+
+    pub(crate) fn status_required(mut self) -> Self {
+        if let BodyType::H2 { status_required, .. } = &mut self.kind {
+            *status_required = true;
+        }
+        self
+    }
+
+    pub(crate) fn in_flight(mut self, in_flight: InFlight) -> Self {
+        self.in_flight = Some(in_flight);
+        self
+    }
+
+    pub(crate) fn with_trailers(mut self, trailers: MetadataMap) -> Self {
+        self.trailers = Some(trailers);
+        self
+    }
+
+    pub(crate) fn trailers(&self) -> Option<&MetadataMap> {
+        self.trailers.as_ref()
     }
 
-    /*
-    pub fn h2(recv: h2::RecvStream) -> Self {
-        let body = Body::new(BodyType::H2 {
-            recv,
-        });
+    pub async fn data(&mut self) -> Option<Result<Bytes, Status>> {
+        future::poll_fn(|cx| self.poll_data(cx)).await
+    }
+
+    pub fn poll_data(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Status>>> {
+        let polled = match &mut self.kind {
+            BodyType::Once(data) => return Poll::Ready(data.take().map(Ok)),
+            BodyType::H2 {
+                recv,
+                data_done,
+                status_required,
+            } => poll_h2(recv, data_done, *status_required, &mut self.trailers, cx),
+            BodyType::Stream(stream) => return stream.as_mut().poll_next(cx),
+        };
+
+        match polled {
+            Poll::Ready(Some(Ok(data))) => Poll::Ready(Some(Ok(data))),
+            Poll::Ready(done) => {
+                self.kind = BodyType::Once(None);
+                Poll::Ready(done)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
 
-        body
+fn poll_h2(
+    recv: &mut RecvStream,
+    data_done: &mut bool,
+    status_required: bool,
+    metadata: &mut Option<MetadataMap>,
+    cx: &mut Context<'_>,
+) -> Poll<Option<Result<Bytes, Status>>> {
+    if !*data_done {
+        match recv.poll_data(cx) {
+            Poll::Ready(Some(Ok(data))) => {
+                let _ = recv.flow_control().release_capacity(data.len());
+                return Poll::Ready(Some(Ok(data)));
+            }
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(Status::from_error(e.into())))),
+            Poll::Ready(None) => *data_done = true,
+            Poll::Pending => return Poll::Pending,
+        }
     }
-    */
 
-    pub fn data(self) -> Option<Bytes> {
-        self.data
+    // a failed call reports its status in the trailers once the data is done
+    match recv.poll_trailers(cx) {
+        Poll::Ready(Ok(Some(trailers))) => match Status::from_header_map(&trailers) {
+            Some(status) if status.code() != Code::Ok => Poll::Ready(Some(Err(status))),
+            Some(status) => {
+                *metadata = Some(status.metadata().clone());
+                Poll::Ready(None)
+            }
+            None if status_required => Poll::Ready(Some(Err(missing_status()))),
+            None => Poll::Ready(None),
+        },
+        // a truncated response, or a proxy that dropped the trailers, must
+        // not pass for a successful call
+        Poll::Ready(Ok(None)) if status_required => Poll::Ready(Some(Err(missing_status()))),
+        Poll::Ready(Ok(None)) => Poll::Ready(None),
+        Poll::Ready(Err(e)) => Poll::Ready(Some(Err(Status::from_error(e.into())))),
+        Poll::Pending => Poll::Pending,
     }
+}
+
+fn missing_status() -> Status {
+    Status::internal("missing grpc-status in trailers")
+}
+
+impl Stream for Body {
+    type Item = Result<Bytes, Status>;
 
-    /*
-    pub async fn get_data(mut self) -> Option<Result<Bytes, h2::Error>> {
-        self.recv.data().await
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_data(cx)
     }
-    */
 }
@@ -3,7 +3,7 @@ pub mod response;
 pub mod body;
 pub mod status;
 
-pub use request::{IntoRequest, Request};
+pub use request::{IntoRequest, IntoStreamingRequest, Request};
 pub use response::Response;
 
 pub use status::{Code, Status};
@@ -1,16 +1,20 @@
 use crate::metadata::{MetadataMap};
+use cynthia::runtime::stream::Stream;
+use http::Extensions;
 
 #[derive(Debug)]
 pub struct Request<T> {
     metadata: MetadataMap,
     payload: T,
+    extensions: Extensions,
 }
 
 impl<T> Request<T> {
     pub fn new(payload: T) -> Self {
         Request {
             metadata: MetadataMap::new(),
-            payload
+            payload,
+            extensions: Extensions::new(),
         }
     }
 
@@ -30,6 +34,14 @@ impl<T> Request<T> {
         &mut self.metadata
     }
 
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     pub fn into_inner(self) -> T {
         self.payload
     }
@@ -42,6 +54,7 @@ impl<T> Request<T> {
         Self {
             metadata: MetadataMap::from_headers(parts.headers),
             payload,
+            extensions: parts.extensions,
         }
     } 
 
@@ -60,6 +73,7 @@ impl<T> Request<T> {
         *request.version_mut() = http::Version::HTTP_2;
         *request.method_mut() = http::Method::POST;
         *request.uri_mut() = uri;
+        *request.extensions_mut() = self.extensions;
         *request.headers_mut() = match sanitize_headers {
             SanitizeHeaders::Yes => self.metadata.into_sanitized_headers(),
             SanitizeHeaders::No => self.metadata.into_headers(),
@@ -77,6 +91,7 @@ impl<T> Request<T> {
         Request {
             metadata: self.metadata,
             payload,
+            extensions: self.extensions,
         }
     }
 }
@@ -103,6 +118,37 @@ impl<T> IntoRequest<T> for Request<T> {
     }
 }
 
+pub trait IntoStreamingRequest: sealed::Sealed {
+    type Stream: Stream<Item = Self::Message> + Send + 'static;
+    type Message;
+
+    fn into_streaming_request(self) -> Request<Self::Stream>;
+}
+
+impl<T> IntoStreamingRequest for T
+where
+    T: Stream + Send + 'static,
+{
+    type Stream = T;
+    type Message = T::Item;
+
+    fn into_streaming_request(self) -> Request<Self> {
+        Request::new(self)
+    }
+}
+
+impl<T> IntoStreamingRequest for Request<T>
+where
+    T: Stream + Send + 'static,
+{
+    type Stream = T;
+    type Message = T::Item;
+
+    fn into_streaming_request(self) -> Self {
+        self
+    }
+}
+
 pub enum SanitizeHeaders {
     Yes,
     No,
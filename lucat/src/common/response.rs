@@ -1,16 +1,22 @@
 use crate::{metadata::MetadataMap};
+use http::Extensions;
 
 #[derive(Debug)]
 pub struct Response<T> {
     metadata: MetadataMap,
+    // sent after the payload, next to `grpc-status`
+    trailers: MetadataMap,
     payload: T,
+    extensions: Extensions,
 }
 
 impl<T> Response<T> {
     pub fn new(payload: T) -> Self {
         Response {
             metadata: MetadataMap::new(),
+            trailers: MetadataMap::new(),
             payload,
+            extensions: Extensions::new(),
         }
     }
 
@@ -30,6 +36,22 @@ impl<T> Response<T> {
         &mut self.metadata
     }
 
+    pub fn trailers(&self) -> &MetadataMap {
+        &self.trailers
+    }
+
+    pub fn trailers_mut(&mut self) -> &mut MetadataMap {
+        &mut self.trailers
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     pub fn into_inner(self) -> T {
         self.payload
     }
@@ -41,7 +63,9 @@ impl<T> Response<T> {
     pub fn from_parts(metadata: MetadataMap, payload: T) -> Self {
         Self {
             metadata,
+            trailers: MetadataMap::new(),
             payload,
+            extensions: Extensions::new(),
         }
     }
 
@@ -49,7 +73,9 @@ impl<T> Response<T> {
         let (head, payload) = res.into_parts();
         Response {
             metadata: MetadataMap::from_headers(head.headers),
+            trailers: MetadataMap::new(),
             payload,
+            extensions: head.extensions,
         }
     }
 
@@ -58,6 +84,7 @@ impl<T> Response<T> {
 
         *res.version_mut() = http::Version::HTTP_2;
         *res.headers_mut() = self.metadata.into_sanitized_headers();
+        *res.extensions_mut() = self.extensions;
 
         res
     }
@@ -69,7 +96,9 @@ impl<T> Response<T> {
         let payload = f(self.payload);
         Response {
             metadata: self.metadata,
+            trailers: self.trailers,
             payload,
+            extensions: self.extensions,
         }
     }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use crate::metadata::MetadataMap;
 use bytes::Bytes;
 use http::header::{HeaderMap, HeaderValue};
+use nephele::proto::h2;
 use percent_encoding::{percent_decode, percent_encode, AsciiSet, CONTROLS};
 use std::{borrow::Cow, error::Error, fmt};
 use tracing::{debug, trace, warn};
@@ -162,10 +163,12 @@ impl Status {
         Status::new(Code::Unauthenticated, message)
     }
 
-    #[cfg_attr(not(feature = "transport"), allow(dead_code))]
     pub(crate) fn from_error(err: Box<dyn Error + Send + Sync + 'static>) -> Status {
-        Status::try_from_error(err)
-            .unwrap_or_else(|err| Status::new(Code::Unknown, err.to_string()))
+        Status::try_from_error(err).unwrap_or_else(|err| {
+            let mut status = Status::new(Code::Unknown, err.to_string());
+            status.source = Some(err);
+            status
+        })
     }
 
     pub(crate) fn try_from_error(
@@ -178,10 +181,18 @@ impl Status {
             Err(err) => err,
         };
 
-        #[cfg(feature = "transport")]
         let err = match err.downcast::<h2::Error>() {
             Ok(h2) => {
-                return Ok(Status::from_h2_error(&*h2));
+                return Ok(Status::from_h2_error(&h2));
+            }
+            Err(err) => err,
+        };
+
+        let err = match err.downcast::<std::io::Error>() {
+            Ok(io) => {
+                let mut status = Status::from(std::io::Error::new(io.kind(), io.to_string()));
+                status.source = Some(io);
+                return Ok(status);
             }
             Err(err) => err,
         };
@@ -193,8 +204,7 @@ impl Status {
         Err(err)
     }
 
-    #[cfg(feature = "transport")]
-    fn from_h2_error(err: &h2::Error) -> Status {
+    pub(crate) fn from_h2_error(err: &h2::Error) -> Status {
         // See https://github.com/grpc/grpc/blob/3977c30/doc/PROTOCOL-HTTP2.md#errors
         let code = match err.reason() {
             Some(h2::Reason::NO_ERROR)
@@ -221,7 +231,6 @@ impl Status {
         status
     }
 
-    #[cfg(feature = "transport")]
     fn to_h2_error(&self) -> h2::Error {
         let reason = match self.code {
             Code::Cancelled => h2::Reason::CANCEL,
@@ -239,52 +248,60 @@ impl Status {
         Status::from_error(err)
     }
 
+    // Parses the status a peer sent in `header_map`, `None` when there is no
+    // `grpc-status`. Whatever the peer sent, this never panics: headers that
+    // can't be parsed give an `Unknown` or `Internal` status instead.
     pub fn from_header_map(header_map: &HeaderMap) -> Option<Status> {
-        header_map.get(GRPC_STATUS_HEADER_CODE).map(|code| {
-            let code = Code::from_bytes(code.as_ref());
-            let error_message = header_map
-                .get(GRPC_STATUS_MESSAGE_HEADER)
-                .map(|header| {
-                    percent_decode(header.as_bytes())
-                        .decode_utf8()
-                        .map(|cow| cow.to_string())
-                })
-                .unwrap_or_else(|| Ok(String::new()));
-
-            let details = header_map
-                .get(GRPC_STATUS_DETAILS_HEADER)
-                .map(|h| {
-                    base64::decode(h.as_bytes())
-                        .expect("Invalid status header, expected base64 encoded value")
-                })
-                .map(Bytes::from)
-                .unwrap_or_else(Bytes::new);
-
-            let mut other_headers = header_map.clone();
-            other_headers.remove(GRPC_STATUS_HEADER_CODE);
-            other_headers.remove(GRPC_STATUS_MESSAGE_HEADER);
-            other_headers.remove(GRPC_STATUS_DETAILS_HEADER);
-
-            match error_message {
-                Ok(message) => Status {
-                    code,
-                    message,
-                    details,
-                    metadata: MetadataMap::from_headers(other_headers),
-                    source: None,
-                },
-                Err(err) => {
-                    warn!("Error deserializing status message header: {}", err);
-                    Status {
-                        code: Code::Unknown,
-                        message: format!("Error deserializing status message header: {}", err),
-                        details,
-                        metadata: MetadataMap::from_headers(other_headers),
-                        source: None,
-                    }
-                }
+        let code = header_map.get(GRPC_STATUS_HEADER_CODE)?;
+
+        let mut other_headers = header_map.clone();
+        other_headers.remove(GRPC_STATUS_HEADER_CODE);
+        other_headers.remove(GRPC_STATUS_MESSAGE_HEADER);
+        other_headers.remove(GRPC_STATUS_DETAILS_HEADER);
+        let metadata = MetadataMap::from_headers(other_headers);
+
+        let status = match Status::parse_header_map(code, header_map) {
+            Ok((code, message, details)) => Status {
+                code,
+                message,
+                details,
+                metadata,
+                source: None,
+            },
+            Err(status) => {
+                warn!("Error deserializing status headers: {}", status.message);
+                Status { metadata, ..status }
             }
-        })
+        };
+        Some(status)
+    }
+
+    fn parse_header_map(
+        code: &HeaderValue,
+        header_map: &HeaderMap,
+    ) -> Result<(Code, String, Bytes), Status> {
+        let code = Code::from_bytes(code.as_bytes());
+
+        let message = match header_map.get(GRPC_STATUS_MESSAGE_HEADER) {
+            Some(header) => percent_decode(header.as_bytes())
+                .decode_utf8()
+                .map(|cow| cow.to_string())
+                .map_err(|err| {
+                    Status::unknown(format!("Error deserializing status message header: {}", err))
+                })?,
+            None => String::new(),
+        };
+
+        let details = match header_map.get(GRPC_STATUS_DETAILS_HEADER) {
+            Some(header) => base64::decode(header.as_bytes())
+                .map(Bytes::from)
+                .map_err(|err| {
+                    Status::internal(format!("Error deserializing status details header: {}", err))
+                })?,
+            None => Bytes::new(),
+        };
+
+        Ok((code, message, details))
     }
 
     pub fn code(&self) -> Code {
@@ -379,11 +396,6 @@ fn find_status_in_source_chain(err: &(dyn Error + 'static)) -> Option<Status> {
             });
         }
 
-        #[cfg(feature = "transport")]
-        if let Some(timeout) = err.downcast_ref::<crate::transport::TimeoutExpired>() {
-            return Some(Status::cancelled(timeout.to_string()));
-        }
-
         source = err.source();
     }
 
@@ -421,14 +433,12 @@ pub fn invalid_header_value_byte<Error: fmt::Display>(err: Error) -> Status {
     )
 }
 
-#[cfg(feature = "transport")]
 impl From<h2::Error> for Status {
     fn from(err: h2::Error) -> Self {
         Status::from_h2_error(&err)
     }
 }
 
-#[cfg(feature = "transport")]
 impl From<Status> for h2::Error {
     fn from(status: Status) -> Self {
         status.to_h2_error()
@@ -1,87 +1,173 @@
 use http::{
     uri::{PathAndQuery},
 };
+use bytes::Bytes;
+use cynthia::runtime::stream::{Stream, StreamExt};
+use std::mem;
 
-use crate::codec::{Codec, Decoder, Encoder};
+use crate::codec::{self, Codec, ContentType, Decoder, Encoder, MessageSizes, Streaming};
+use crate::codec::DEFAULT_MAX_ENCODING_MESSAGE_SIZE;
 use crate::common::{Body, Request, Response};
-use crate::{Status, Code};
-use prost;
+use crate::metadata::MetadataMap;
+use crate::Status;
 
 pub struct Rpc<T> {
     inner: T,
+    max_decoding_message_size: Option<usize>,
+    max_encoding_message_size: Option<usize>,
 }
 
 fn map_request<B, E>(
     encoder: &mut E,
     request: Request<B>,
+    max: usize,
 ) -> Result<Request<Body>, Status>
 where
-    B: prost::Message + Send + Sync + 'static,
+    B: Send + Sync + 'static,
     E: Encoder<Item = B, Error = Status> + Send + Sync + 'static,
 {
-    let res = request.into_inner();
-    let output_bytes = encoder.encode(res).unwrap();
+    let mut request = request.map(|message| {
+        encoder
+            .encode(message)
+            .and_then(|bytes| codec::encode_limited_frame(bytes, max).map_err(Status::from))
+    });
+    let frame = mem::replace(request.get_mut(), Ok(Bytes::new()))?;
 
-    let gd = Request::new(Body::new(Some(output_bytes)));
-    Ok(gd)
+    Ok(request.map(|_| Body::new(Some(frame))))
+}
+
+// Reads the only message of a response, whose metadata then holds both the
+// headers and the trailers of the call. The trailers are kept on their own too.
+async fn single_message<M>(response: Response<Streaming<M>>) -> Result<Response<M>, Status> {
+    let (metadata, mut stream) = response.into_parts();
+
+    let message = match stream.message().await? {
+        Some(message) => message,
+        None => return Err(Status::internal("missing response message")),
+    };
+    if stream.message().await?.is_some() {
+        return Err(Status::internal("expected a single response message, got more"));
+    }
+
+    let trailers = stream.trailers().cloned().unwrap_or_default();
+    let mut headers = metadata.into_headers();
+    headers.extend(trailers.clone().into_headers());
+
+    let mut response = Response::from_parts(MetadataMap::from_headers(headers), message);
+    *response.trailers_mut() = trailers;
+    Ok(response)
 }
 
 impl<T> Rpc<T> {
     pub fn new(inner: T) -> Self {
         Self {
             inner,
+            max_decoding_message_size: None,
+            max_encoding_message_size: None,
         }
     }
 
+    // Limits set here win over those of the endpoint the call goes through.
+    pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+        self.max_decoding_message_size = Some(limit);
+        self
+    }
+
+    pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+        self.max_encoding_message_size = Some(limit);
+        self
+    }
+
+    fn max_encoding(&self) -> usize {
+        self.max_encoding_message_size.unwrap_or(DEFAULT_MAX_ENCODING_MESSAGE_SIZE)
+    }
+
+    fn decode<D>(&self, response: Response<Body>, decoder: D) -> Response<Streaming<D::Item>>
+    where
+        D: Decoder<Error = Status> + Send + 'static,
+    {
+        let max = MessageSizes::max_decoding(self.max_decoding_message_size, response.extensions());
+        response.map(|body| Streaming::new(body, decoder).max_message_size(max))
+    }
+
     pub async fn unary<M1, M2, C>(
-        &mut self, 
+        &mut self,
         req: Request<M1>,
-        _path: PathAndQuery,
+        path: PathAndQuery,
         mut codec: C,
     ) -> Result<Response<M2>, Status>
     where
         T: crate::SimpleInstantService + Send + Sync + 'static,
         C: Codec<Encode = M1, Decode = M2>,
-        M1: Send + Sync + 'static + prost::Message,
+        M1: Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
-        let req = map_request(&mut codec.encoder(), req);
-
-        match req {
-            Ok(request) => {
-                let response = self.inner.call(request).await;
-                match response {
-                    Ok(res) => {
-                        let payload = res.into_inner().data();
-                        match payload {
-                            Some(data) => {
-                                let mut decoder = codec.decoder();
-                                let decoded_response = decoder.decode(data);
-                                match decoded_response {
-                                    Ok(Some(msg)) => {
-                                        Ok(Response::new(msg))
-                                    }
-                                    Ok(None) => {
-                                        Err(Status::new(Code::OutOfRange, "error"))
-                                    }
-                                    Err(_e) => {
-                                        Err(Status::new(Code::OutOfRange, "error"))
-                                    }
-                                }
-                            }
-                            None => {
-                                Err(Status::new(Code::OutOfRange, "error"))
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        Err(Status::new(Code::OutOfRange, "error"))
-                    }
-                }
-            }
-            Err(_) => {
-                Err(Status::new(Code::OutOfRange, "error"))
-            }
-        }
+        let mut request = map_request(&mut codec.encoder(), req, self.max_encoding())?;
+        request.extensions_mut().insert(path);
+        request.extensions_mut().insert(ContentType::of::<C>());
+
+        let response = self.inner.call(request).await.map_err(Status::from_error)?;
+        let response = self.decode(response, codec.decoder());
+        single_message(response).await
+    }
+
+    pub async fn server_streaming<M1, M2, C>(
+        &mut self,
+        req: Request<M1>,
+        path: PathAndQuery,
+        mut codec: C,
+    ) -> Result<Response<Streaming<M2>>, Status>
+    where
+        T: crate::SimpleInstantService + Send + Sync + 'static,
+        C: Codec<Encode = M1, Decode = M2>,
+        M1: Send + Sync + 'static,
+        M2: Send + Sync + 'static,
+    {
+        let mut request = map_request(&mut codec.encoder(), req, self.max_encoding())?;
+        request.extensions_mut().insert(path);
+        request.extensions_mut().insert(ContentType::of::<C>());
+
+        let response = self.inner.call(request).await.map_err(Status::from_error)?;
+        Ok(self.decode(response, codec.decoder()))
+    }
+
+    pub async fn client_streaming<S, M1, M2, C>(
+        &mut self,
+        req: Request<S>,
+        path: PathAndQuery,
+        codec: C,
+    ) -> Result<Response<M2>, Status>
+    where
+        T: crate::SimpleInstantService + Send + Sync + 'static,
+        S: Stream<Item = M1> + Send + 'static,
+        C: Codec<Encode = M1, Decode = M2>,
+        M1: Send + Sync + 'static,
+        M2: Send + Sync + 'static,
+    {
+        let response = self.streaming(req, path, codec).await?;
+        single_message(response).await
+    }
+
+    pub async fn streaming<S, M1, M2, C>(
+        &mut self,
+        req: Request<S>,
+        path: PathAndQuery,
+        mut codec: C,
+    ) -> Result<Response<Streaming<M2>>, Status>
+    where
+        T: crate::SimpleInstantService + Send + Sync + 'static,
+        S: Stream<Item = M1> + Send + 'static,
+        C: Codec<Encode = M1, Decode = M2>,
+        M1: Send + Sync + 'static,
+        M2: Send + Sync + 'static,
+    {
+        let encoder = codec.encoder();
+        let max = self.max_encoding();
+        let mut request = req.map(|stream| Body::stream(codec::encode(encoder, stream.map(Ok), max)));
+        request.extensions_mut().insert(path);
+        request.extensions_mut().insert(ContentType::of::<C>());
+
+        let response = self.inner.call(request).await.map_err(Status::from_error)?;
+        Ok(self.decode(response, codec.decoder()))
     }
 }
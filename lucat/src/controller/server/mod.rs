@@ -2,4 +2,6 @@ pub mod rpc;
 pub mod service;
 
 pub use self::rpc::Rpc;
-pub use self::service::{UnaryService};
+pub use self::service::{
+    ClientStreamingService, ServerStreamingService, StreamingService, UnaryService,
+};
@@ -1,34 +1,46 @@
+use bytes::Bytes;
+use std::mem;
+
 use crate::common::{Body, Request, Response};
-use crate::controller::server::UnaryService;
-use crate::codec::{Codec, Decoder, Encoder};
+use crate::controller::server::{
+    ClientStreamingService, ServerStreamingService, StreamingService, UnaryService,
+};
+use crate::codec::{self, Codec, ContentType, Encoder, MessageSizes, Streaming};
 use crate::Status;
-use prost;
 
 fn map_response<B, E>(
     encoder: &mut E,
     response: Result<Response<B>, Status>,
-) -> Result<Response<Body>, Status>
+    max: usize,
+) -> Response<Body>
 where
-    B: prost::Message + Send + Sync + 'static,
+    B: Send + Sync + 'static,
     E: Encoder<Item = B, Error = Status> + Send + Sync + 'static,
 {
     let response = match response {
         Ok(r) => r,
-        Err(_) => return Ok(Response::new(Body::new(None))),
+        Err(status) => return Response::new(Body::error(status)),
     };
 
-    let res = response.into_inner();
-    let output_bytes = encoder.encode(res).unwrap();
+    let mut response = response.map(|message| {
+        encoder
+            .encode(message)
+            .and_then(|bytes| codec::encode_limited_frame(bytes, max).map_err(Status::from))
+    });
 
-    let gd = Response::new(Body::new(Some(output_bytes)));
-    Ok(gd)
+    match mem::replace(response.get_mut(), Ok(Bytes::new())) {
+        Ok(frame) => response.map(|_| Body::new(Some(frame))),
+        Err(status) => Response::new(Body::error(status)),
+    }
 }
 
 pub struct Rpc<T> {
     codec: T,
+    max_decoding_message_size: Option<usize>,
+    max_encoding_message_size: Option<usize>,
 }
 
-impl<T> Rpc<T> 
+impl<T> Rpc<T>
 where
     T: Codec,
     T::Encode: Sync,
@@ -36,44 +48,150 @@ where
     pub fn new(codec: T) -> Self {
         Self {
             codec,
+            max_decoding_message_size: None,
+            max_encoding_message_size: None,
         }
     }
 
+    // Limits set here win over those of the server the call arrived on.
+    pub fn apply_max_message_size_config(
+        mut self,
+        max_decoding: Option<usize>,
+        max_encoding: Option<usize>,
+    ) -> Self {
+        self.max_decoding_message_size = max_decoding;
+        self.max_encoding_message_size = max_encoding;
+        self
+    }
+
     pub async fn unary<S>(
         &mut self,
         mut service: S,
         req: Request<Body>
-    ) -> Response<Body> 
+    ) -> Response<Body>
     where
         S: UnaryService<T::Decode, Response = T::Encode>,
-        <T as Codec>::Encode: prost::Message,
     {
-        let req = req.into_inner().data();
-        match req {
-            Some(data) => {
-                let mut decoder = self.codec.decoder();
-                let decoded_request = decoder.decode(data);
-
-                match decoded_request {
-                    Ok(Some(msg)) => {
-                        let dec_req = Request::new(msg);
-                        let output = service.call(dec_req).await;
-
-                        let body = map_response(&mut self.codec.encoder(), output);
-                        let gd = body.unwrap();
-                        gd
-                    }
-                    Ok(None) => {
-                        Response::new(Body::new(None))
-                    }
-                    Err(_e) => {
-                        Response::new(Body::new(None))
-                    }
-                }
+        if let Err(status) = self.negotiate(&req) {
+            return self.with_content_type(Response::new(Body::error(status)));
+        }
+        let max = MessageSizes::max_encoding(self.max_encoding_message_size, req.extensions());
+        let dec_req = match self.decode_one(req).await {
+            Ok(req) => req,
+            Err(status) => return self.with_content_type(Response::new(Body::error(status))),
+        };
+
+        let output = service.call(dec_req).await;
+
+        let body = map_response(&mut self.codec.encoder(), output, max);
+        self.with_content_type(body)
+    }
+
+    pub async fn server_streaming<S>(
+        &mut self,
+        mut service: S,
+        req: Request<Body>
+    ) -> Response<Body>
+    where
+        S: ServerStreamingService<T::Decode, Response = T::Encode>,
+        S::ResponseStream: Send + 'static,
+    {
+        if let Err(status) = self.negotiate(&req) {
+            return self.with_content_type(Response::new(Body::error(status)));
+        }
+        let max = MessageSizes::max_encoding(self.max_encoding_message_size, req.extensions());
+        let req = match self.decode_one(req).await {
+            Ok(req) => req,
+            Err(status) => return self.with_content_type(Response::new(Body::error(status))),
+        };
+
+        let response = match service.call(req).await {
+            Ok(response) => {
+                let encoder = self.codec.encoder();
+                response.map(|stream| Body::stream(codec::encode(encoder, stream, max)))
             }
-            None => {
-                Response::new(Body::new(None))
+            Err(status) => Response::new(Body::error(status)),
+        };
+        self.with_content_type(response)
+    }
+
+    pub async fn client_streaming<S>(
+        &mut self,
+        mut service: S,
+        req: Request<Body>
+    ) -> Response<Body>
+    where
+        S: ClientStreamingService<T::Decode, Response = T::Encode>,
+    {
+        if let Err(status) = self.negotiate(&req) {
+            return self.with_content_type(Response::new(Body::error(status)));
+        }
+        let max = MessageSizes::max_encoding(self.max_encoding_message_size, req.extensions());
+        let req = self.streaming_request(req);
+        let output = service.call(req).await;
+
+        let response = map_response(&mut self.codec.encoder(), output, max);
+        self.with_content_type(response)
+    }
+
+    pub async fn streaming<S>(
+        &mut self,
+        mut service: S,
+        req: Request<Body>
+    ) -> Response<Body>
+    where
+        S: StreamingService<T::Decode, Response = T::Encode>,
+        S::ResponseStream: Send + 'static,
+    {
+        if let Err(status) = self.negotiate(&req) {
+            return self.with_content_type(Response::new(Body::error(status)));
+        }
+        let max = MessageSizes::max_encoding(self.max_encoding_message_size, req.extensions());
+        let req = self.streaming_request(req);
+
+        let response = match service.call(req).await {
+            Ok(response) => {
+                let encoder = self.codec.encoder();
+                response.map(|stream| Body::stream(codec::encode(encoder, stream, max)))
             }
+            Err(status) => Response::new(Body::error(status)),
+        };
+        self.with_content_type(response)
+    }
+
+    // lets the codec pick its format from the content-type of the call
+    fn negotiate(&mut self, req: &Request<Body>) -> Result<(), Status> {
+        match req.extensions().get::<ContentType>() {
+            Some(content_type) => self.codec.negotiate(&content_type.0),
+            None => Ok(()),
+        }
+    }
+
+    // services that pass calls through, like the proxy, may have set the
+    // content-type of the response themselves
+    fn with_content_type(&self, mut response: Response<Body>) -> Response<Body> {
+        if response.extensions().get::<ContentType>().is_none() {
+            response
+                .extensions_mut()
+                .insert(ContentType(self.codec.content_type()));
         }
+        response
     }
-}
\ No newline at end of file
+
+    // keeps the metadata and extensions of `req` around the decoded message
+    async fn decode_one(&mut self, mut req: Request<Body>) -> Result<Request<T::Decode>, Status> {
+        let body = std::mem::replace(req.get_mut(), Body::empty());
+        let max = MessageSizes::max_decoding(self.max_decoding_message_size, req.extensions());
+        let mut stream = Streaming::new(body, self.codec.decoder()).max_message_size(max);
+        match stream.message().await? {
+            Some(msg) => Ok(req.map(|_| msg)),
+            None => Err(Status::internal("missing request message")),
+        }
+    }
+
+    fn streaming_request(&mut self, req: Request<Body>) -> Request<Streaming<T::Decode>> {
+        let max = MessageSizes::max_decoding(self.max_decoding_message_size, req.extensions());
+        let decoder = self.codec.decoder();
+        req.map(|body| Streaming::new(body, decoder).max_message_size(max))
+    }
+}
@@ -0,0 +1,448 @@
+use prost::{DecodeError, Message};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct DescriptorPool {
+    inner: Arc<Pool>,
+}
+
+#[derive(Default)]
+struct Pool {
+    files: Vec<FileDescriptorProto>,
+    messages: HashMap<String, Entry<DescriptorProto>>,
+    enums: HashMap<String, Entry<EnumDescriptorProto>>,
+    services: Vec<(String, ServiceDescriptorProto)>,
+}
+
+struct Entry<T> {
+    proto: T,
+    file: usize,
+}
+
+impl DescriptorPool {
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecodeError> {
+        Ok(DescriptorPool::new(FileDescriptorSet::decode(encoded)?))
+    }
+
+    pub fn new(set: FileDescriptorSet) -> Self {
+        let mut pool = Pool::default();
+
+        for file in set.file {
+            if pool.files.iter().any(|known| known.name == file.name) {
+                continue;
+            }
+
+            let index = pool.files.len();
+            let prefix = match file.package() {
+                "" => String::new(),
+                package => format!("{}.", package),
+            };
+
+            for message in file.message_type.iter() {
+                pool.add_message(&prefix, message, index);
+            }
+            for enumeration in file.enum_type.iter() {
+                pool.add_enum(&prefix, enumeration, index);
+            }
+            for service in file.service.iter() {
+                pool.services
+                    .push((format!("{}{}", prefix, service.name()), service.clone()));
+            }
+
+            pool.files.push(file);
+        }
+
+        DescriptorPool {
+            inner: Arc::new(pool),
+        }
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = ServiceDescriptor> + '_ {
+        (0..self.inner.services.len()).map(move |index| ServiceDescriptor {
+            pool: self.clone(),
+            index,
+        })
+    }
+
+    pub fn get_service_by_name(&self, name: &str) -> Option<ServiceDescriptor> {
+        self.services().find(|service| service.full_name() == name)
+    }
+
+    pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor> {
+        let name = name.trim_start_matches('.');
+        self.inner.messages.get(name).map(|_| MessageDescriptor {
+            pool: self.clone(),
+            name: name.to_string(),
+        })
+    }
+
+    pub fn get_enum_by_name(&self, name: &str) -> Option<EnumDescriptor> {
+        let name = name.trim_start_matches('.');
+        self.inner.enums.get(name).map(|_| EnumDescriptor {
+            pool: self.clone(),
+            name: name.to_string(),
+        })
+    }
+}
+
+impl Pool {
+    fn add_message(&mut self, prefix: &str, message: &DescriptorProto, file: usize) {
+        let name = format!("{}{}", prefix, message.name());
+        let nested = format!("{}.", name);
+
+        for message in message.nested_type.iter() {
+            self.add_message(&nested, message, file);
+        }
+        for enumeration in message.enum_type.iter() {
+            self.add_enum(&nested, enumeration, file);
+        }
+
+        let proto = message.clone();
+        self.messages.insert(name, Entry { proto, file });
+    }
+
+    fn add_enum(&mut self, prefix: &str, enumeration: &EnumDescriptorProto, file: usize) {
+        let name = format!("{}{}", prefix, enumeration.name());
+        let proto = enumeration.clone();
+        self.enums.insert(name, Entry { proto, file });
+    }
+}
+
+impl fmt::Debug for DescriptorPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DescriptorPool")
+            .field("files", &self.inner.files.len())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct ServiceDescriptor {
+    pool: DescriptorPool,
+    index: usize,
+}
+
+impl ServiceDescriptor {
+    fn proto(&self) -> &ServiceDescriptorProto {
+        &self.pool.inner.services[self.index].1
+    }
+
+    pub fn name(&self) -> &str {
+        self.proto().name()
+    }
+
+    pub fn full_name(&self) -> &str {
+        &self.pool.inner.services[self.index].0
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodDescriptor> + '_ {
+        (0..self.proto().method.len()).map(move |index| MethodDescriptor {
+            service: self.clone(),
+            index,
+        })
+    }
+
+    pub fn get_method_by_name(&self, name: &str) -> Option<MethodDescriptor> {
+        self.methods().find(|method| method.name() == name)
+    }
+}
+
+#[derive(Clone)]
+pub struct MethodDescriptor {
+    service: ServiceDescriptor,
+    index: usize,
+}
+
+impl MethodDescriptor {
+    fn proto(&self) -> &MethodDescriptorProto {
+        &self.service.proto().method[self.index]
+    }
+
+    pub fn name(&self) -> &str {
+        self.proto().name()
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.service.full_name(), self.name())
+    }
+
+    pub fn path(&self) -> String {
+        format!("/{}/{}", self.service.full_name(), self.name())
+    }
+
+    pub fn input(&self) -> Option<MessageDescriptor> {
+        self.service.pool.get_message_by_name(self.proto().input_type())
+    }
+
+    pub fn output(&self) -> Option<MessageDescriptor> {
+        self.service.pool.get_message_by_name(self.proto().output_type())
+    }
+
+    pub fn is_client_streaming(&self) -> bool {
+        self.proto().client_streaming()
+    }
+
+    pub fn is_server_streaming(&self) -> bool {
+        self.proto().server_streaming()
+    }
+}
+
+#[derive(Clone)]
+pub struct MessageDescriptor {
+    pool: DescriptorPool,
+    name: String,
+}
+
+impl MessageDescriptor {
+    fn entry(&self) -> &Entry<DescriptorProto> {
+        &self.pool.inner.messages[&self.name]
+    }
+
+    pub fn descriptor_proto(&self) -> &DescriptorProto {
+        &self.entry().proto
+    }
+
+    pub fn full_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    pub fn is_map_entry(&self) -> bool {
+        self.descriptor_proto()
+            .options
+            .as_ref()
+            .map(|options| options.map_entry())
+            .unwrap_or(false)
+    }
+
+    fn is_proto3(&self) -> bool {
+        self.pool.inner.files[self.entry().file].syntax() == "proto3"
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldDescriptor> + '_ {
+        (0..self.descriptor_proto().field.len()).map(move |index| FieldDescriptor {
+            message: self.clone(),
+            index,
+        })
+    }
+
+    pub fn get_field(&self, number: u32) -> Option<FieldDescriptor> {
+        self.fields().find(|field| field.number() == number)
+    }
+
+    // accepts both the JSON name and the name used in the .proto file
+    pub fn get_field_by_name(&self, name: &str) -> Option<FieldDescriptor> {
+        self.fields()
+            .find(|field| field.json_name() == name || field.name() == name)
+    }
+}
+
+impl PartialEq for MessageDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pool.inner, &other.pool.inner) && self.name == other.name
+    }
+}
+
+impl fmt::Debug for MessageDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageDescriptor").field(&self.name).finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct FieldDescriptor {
+    message: MessageDescriptor,
+    index: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    Message(MessageDescriptor),
+    Enum(EnumDescriptor),
+}
+
+impl FieldDescriptor {
+    pub fn field_descriptor_proto(&self) -> &FieldDescriptorProto {
+        &self.message.descriptor_proto().field[self.index]
+    }
+
+    pub fn name(&self) -> &str {
+        self.field_descriptor_proto().name()
+    }
+
+    pub fn json_name(&self) -> Cow<'_, str> {
+        match self.field_descriptor_proto().json_name.as_ref() {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(to_json_name(self.name())),
+        }
+    }
+
+    pub fn number(&self) -> u32 {
+        self.field_descriptor_proto().number() as u32
+    }
+
+    pub fn kind(&self) -> Kind {
+        let proto = self.field_descriptor_proto();
+        let pool = &self.message.pool;
+
+        match proto.r#type() {
+            Type::Double => Kind::Double,
+            Type::Float => Kind::Float,
+            Type::Int32 => Kind::Int32,
+            Type::Int64 => Kind::Int64,
+            Type::Uint32 => Kind::Uint32,
+            Type::Uint64 => Kind::Uint64,
+            Type::Sint32 => Kind::Sint32,
+            Type::Sint64 => Kind::Sint64,
+            Type::Fixed32 => Kind::Fixed32,
+            Type::Fixed64 => Kind::Fixed64,
+            Type::Sfixed32 => Kind::Sfixed32,
+            Type::Sfixed64 => Kind::Sfixed64,
+            Type::Bool => Kind::Bool,
+            Type::String => Kind::String,
+            Type::Bytes => Kind::Bytes,
+            Type::Enum => match pool.get_enum_by_name(proto.type_name()) {
+                Some(enumeration) => Kind::Enum(enumeration),
+                None => Kind::Int32,
+            },
+            // groups are decoded like embedded messages that happen to use
+            // a different wire framing, which proto3 never produces
+            Type::Message | Type::Group => match pool.get_message_by_name(proto.type_name()) {
+                Some(message) => Kind::Message(message),
+                None => Kind::Bytes,
+            },
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.field_descriptor_proto().label() == Label::Repeated && !self.is_map()
+    }
+
+    pub fn is_map(&self) -> bool {
+        self.field_descriptor_proto().label() == Label::Repeated
+            && match self.kind() {
+                Kind::Message(message) => message.is_map_entry(),
+                _ => false,
+            }
+    }
+
+    pub fn is_packed(&self) -> bool {
+        if !self.is_list() {
+            return false;
+        }
+        if let Kind::String | Kind::Bytes | Kind::Message(_) = self.kind() {
+            return false;
+        }
+
+        match self.field_descriptor_proto().options.as_ref().and_then(|options| options.packed) {
+            Some(packed) => packed,
+            None => self.message.is_proto3(),
+        }
+    }
+
+    pub fn oneof_name(&self) -> Option<&str> {
+        let index = self.field_descriptor_proto().oneof_index? as usize;
+        // proto3 `optional` is a synthetic oneof, not a real one
+        if self.field_descriptor_proto().proto3_optional() {
+            return None;
+        }
+        self.message
+            .descriptor_proto()
+            .oneof_decl
+            .get(index)
+            .map(|oneof| oneof.name())
+    }
+}
+
+impl fmt::Debug for FieldDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FieldDescriptor").field(&self.name()).finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct EnumDescriptor {
+    pool: DescriptorPool,
+    name: String,
+}
+
+impl EnumDescriptor {
+    pub fn enum_descriptor_proto(&self) -> &EnumDescriptorProto {
+        &self.pool.inner.enums[&self.name].proto
+    }
+
+    pub fn full_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_value(&self, number: i32) -> Option<&str> {
+        self.enum_descriptor_proto()
+            .value
+            .iter()
+            .find(|value| value.number() == number)
+            .map(|value| value.name())
+    }
+
+    pub fn get_value_by_name(&self, name: &str) -> Option<i32> {
+        self.enum_descriptor_proto()
+            .value
+            .iter()
+            .find(|value| value.name() == name)
+            .map(|value| value.number())
+    }
+}
+
+impl PartialEq for EnumDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pool.inner, &other.pool.inner) && self.name == other.name
+    }
+}
+
+impl fmt::Debug for EnumDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EnumDescriptor").field(&self.name).finish()
+    }
+}
+
+// what protoc fills in for `json_name`: lowerCamelCase, dropping the underscores
+fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            json_name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
@@ -0,0 +1,726 @@
+use anyhow::{anyhow, bail, Result};
+use bytes::Bytes;
+use serde_json::{Map, Number, Value as Json};
+use std::collections::BTreeMap;
+
+use super::{DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value};
+
+// the range of `google.protobuf.Timestamp`, 0001-01-01 to 9999-12-31
+const MIN_TIMESTAMP_SECONDS: i64 = -62_135_596_800;
+const MAX_TIMESTAMP_SECONDS: i64 = 253_402_300_799;
+// and of `google.protobuf.Duration`, about 10000 years
+const MAX_DURATION_SECONDS: i64 = 315_576_000_000;
+
+// The canonical proto3 JSON mapping, with the special forms of the
+// well-known types.
+pub fn to_json(message: &DynamicMessage) -> Result<Json> {
+    if let Some(json) = well_known_to_json(message)? {
+        return Ok(json);
+    }
+
+    let mut object = Map::new();
+    for (field, value) in message.fields() {
+        let kind = field.kind();
+        let json = match value {
+            Value::List(values) => Json::Array(
+                values
+                    .iter()
+                    .map(|value| value_to_json(&kind, value))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Map(entries) => {
+                let value_kind = map_value_kind(&kind);
+                let mut object = Map::new();
+                for (key, value) in entries {
+                    object.insert(map_key_to_string(key), value_to_json(&value_kind, value)?);
+                }
+                Json::Object(object)
+            }
+            value => value_to_json(&kind, value)?,
+        };
+        object.insert(field.json_name().into_owned(), json);
+    }
+    Ok(Json::Object(object))
+}
+
+pub fn from_json(descriptor: MessageDescriptor, json: &Json) -> Result<DynamicMessage> {
+    if let Some(message) = well_known_from_json(&descriptor, json)? {
+        return Ok(message);
+    }
+
+    let object = match json {
+        Json::Object(object) => object,
+        _ => bail!("expected an object for {}", descriptor.full_name()),
+    };
+
+    let mut message = DynamicMessage::new(descriptor.clone());
+    let mut oneofs = Vec::new();
+    for (name, json) in object {
+        let field = descriptor
+            .get_field_by_name(name)
+            .ok_or_else(|| anyhow!("message {} has no field {:?}", descriptor.full_name(), name))?;
+        // null means unset, except for `google.protobuf.Value` where it is a value
+        if json.is_null() && !is_message(&field.kind(), "google.protobuf.Value") {
+            continue;
+        }
+
+        if let Some(oneof) = field.oneof_name() {
+            if oneofs.iter().any(|set| set == oneof) {
+                bail!("more than one field of oneof {} is set", oneof);
+            }
+            oneofs.push(oneof.to_string());
+        }
+
+        let value =
+            field_from_json(&field, json).map_err(|e| anyhow!("field {}: {}", field.name(), e))?;
+        message.set_field(&field, value)?;
+    }
+    Ok(message)
+}
+
+fn field_from_json(field: &FieldDescriptor, json: &Json) -> Result<Value> {
+    let kind = field.kind();
+
+    if field.is_map() {
+        let object = json
+            .as_object()
+            .ok_or_else(|| anyhow!("expected an object"))?;
+        let entry = match &kind {
+            Kind::Message(entry) => entry,
+            _ => bail!("invalid map field"),
+        };
+        let key_kind = entry.get_field(1).map(|f| f.kind()).unwrap_or(Kind::String);
+        let value_kind = map_value_kind(&kind);
+
+        let mut entries = BTreeMap::new();
+        for (key, value) in object {
+            entries.insert(
+                map_key_from_string(&key_kind, key)?,
+                value_from_json(&value_kind, value)?,
+            );
+        }
+        Ok(Value::Map(entries))
+    } else if field.is_list() {
+        let array = json
+            .as_array()
+            .ok_or_else(|| anyhow!("expected an array"))?;
+        let values = array
+            .iter()
+            .map(|json| value_from_json(&kind, json))
+            .collect::<Result<_>>()?;
+        Ok(Value::List(values))
+    } else {
+        value_from_json(&kind, json)
+    }
+}
+
+fn map_value_kind(kind: &Kind) -> Kind {
+    match kind {
+        Kind::Message(entry) => entry.get_field(2).map(|f| f.kind()).unwrap_or(Kind::Bytes),
+        kind => kind.clone(),
+    }
+}
+
+fn is_message(kind: &Kind, name: &str) -> bool {
+    matches!(kind, Kind::Message(descriptor) if descriptor.full_name() == name)
+}
+
+fn value_to_json(kind: &Kind, value: &Value) -> Result<Json> {
+    let json = match value {
+        Value::Bool(v) => Json::Bool(*v),
+        Value::I32(v) => Json::from(*v),
+        Value::U32(v) => Json::from(*v),
+        // 64-bit integers are quoted so JavaScript readers keep every digit
+        Value::I64(v) => Json::String(v.to_string()),
+        Value::U64(v) => Json::String(v.to_string()),
+        Value::F32(v) => float_to_json(*v as f64),
+        Value::F64(v) => float_to_json(*v),
+        Value::String(v) => Json::String(v.clone()),
+        Value::Bytes(v) => Json::String(base64::encode(v)),
+        Value::EnumNumber(v) => match kind {
+            Kind::Enum(enumeration) if enumeration.full_name() == "google.protobuf.NullValue" => {
+                Json::Null
+            }
+            Kind::Enum(enumeration) => match enumeration.get_value(*v) {
+                Some(name) => Json::String(name.to_string()),
+                None => Json::from(*v),
+            },
+            _ => Json::from(*v),
+        },
+        Value::Message(message) => to_json(message)?,
+        Value::List(values) => Json::Array(
+            values
+                .iter()
+                .map(|v| value_to_json(kind, v))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Map(_) => Json::Null,
+    };
+    Ok(json)
+}
+
+fn float_to_json(v: f64) -> Json {
+    if v.is_nan() {
+        Json::String("NaN".to_string())
+    } else if v.is_infinite() && v > 0.0 {
+        Json::String("Infinity".to_string())
+    } else if v.is_infinite() {
+        Json::String("-Infinity".to_string())
+    } else {
+        Number::from_f64(v).map(Json::Number).unwrap_or(Json::Null)
+    }
+}
+
+fn value_from_json(kind: &Kind, json: &Json) -> Result<Value> {
+    let value = match kind {
+        Kind::Bool => Value::Bool(json.as_bool().ok_or_else(|| anyhow!("expected a bool"))?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(int_from_json(json)?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(int_from_json(json)?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(int_from_json(json)?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(int_from_json(json)?),
+        Kind::Float => Value::F32(float_from_json(json)? as f32),
+        Kind::Double => Value::F64(float_from_json(json)?),
+        Kind::String => Value::String(
+            json.as_str()
+                .ok_or_else(|| anyhow!("expected a string"))?
+                .to_string(),
+        ),
+        Kind::Bytes => {
+            let encoded = json
+                .as_str()
+                .ok_or_else(|| anyhow!("expected a base64 string"))?;
+            // both the standard and the URL-safe alphabet are accepted
+            let decoded = base64::decode(encoded)
+                .or_else(|_| base64::decode_config(encoded, base64::URL_SAFE))?;
+            Value::Bytes(Bytes::from(decoded))
+        }
+        Kind::Enum(enumeration) => match json {
+            Json::Null if enumeration.full_name() == "google.protobuf.NullValue" => {
+                Value::EnumNumber(0)
+            }
+            Json::String(name) => {
+                Value::EnumNumber(enumeration.get_value_by_name(name).ok_or_else(|| {
+                    anyhow!("{} has no value {:?}", enumeration.full_name(), name)
+                })?)
+            }
+            json => Value::EnumNumber(int_from_json(json)?),
+        },
+        Kind::Message(descriptor) => Value::Message(from_json(descriptor.clone(), json)?),
+    };
+    Ok(value)
+}
+
+fn int_from_json<T>(json: &Json) -> Result<T>
+where
+    T: std::str::FromStr + std::convert::TryFrom<i64> + std::convert::TryFrom<u64>,
+{
+    let parsed = match json {
+        Json::String(s) => s.parse().ok(),
+        Json::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (_, Some(v)) => T::try_from(v).ok(),
+            (Some(v), _) => T::try_from(v).ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+    parsed.ok_or_else(|| anyhow!("expected an integer, got {}", json))
+}
+
+fn float_from_json(json: &Json) -> Result<f64> {
+    match json {
+        Json::Number(n) => n.as_f64().ok_or_else(|| anyhow!("expected a number")),
+        Json::String(s) => match s.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            s => Ok(s.parse()?),
+        },
+        json => bail!("expected a number, got {}", json),
+    }
+}
+
+fn map_key_to_string(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(v) => v.to_string(),
+        MapKey::I32(v) => v.to_string(),
+        MapKey::I64(v) => v.to_string(),
+        MapKey::U32(v) => v.to_string(),
+        MapKey::U64(v) => v.to_string(),
+        MapKey::String(v) => v.clone(),
+    }
+}
+
+fn map_key_from_string(kind: &Kind, key: &str) -> Result<MapKey> {
+    let key = match kind {
+        Kind::Bool => MapKey::Bool(key.parse()?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => MapKey::I32(key.parse()?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => MapKey::I64(key.parse()?),
+        Kind::Uint32 | Kind::Fixed32 => MapKey::U32(key.parse()?),
+        Kind::Uint64 | Kind::Fixed64 => MapKey::U64(key.parse()?),
+        Kind::String => MapKey::String(key.to_string()),
+        _ => bail!("invalid map key type"),
+    };
+    Ok(key)
+}
+
+fn get(message: &DynamicMessage, number: u32) -> Option<&Value> {
+    message
+        .descriptor()
+        .get_field(number)
+        .and_then(|field| message.get_field(&field))
+}
+
+fn set(message: &mut DynamicMessage, number: u32, value: Value) -> Result<()> {
+    let field = message.descriptor().get_field(number).ok_or_else(|| {
+        anyhow!(
+            "{} has no field {}",
+            message.descriptor().full_name(),
+            number
+        )
+    })?;
+    message.set_field(&field, value)?;
+    Ok(())
+}
+
+fn field_kind(descriptor: &MessageDescriptor, number: u32) -> Result<Kind> {
+    descriptor
+        .get_field(number)
+        .map(|field| field.kind())
+        .ok_or_else(|| anyhow!("{} has no field {}", descriptor.full_name(), number))
+}
+
+fn well_known_to_json(message: &DynamicMessage) -> Result<Option<Json>> {
+    let descriptor = message.descriptor();
+    let json = match descriptor.full_name() {
+        "google.protobuf.Timestamp" => {
+            let seconds = match get(message, 1) {
+                Some(Value::I64(seconds)) => *seconds,
+                _ => 0,
+            };
+            let nanos = match get(message, 2) {
+                Some(Value::I32(nanos)) => *nanos,
+                _ => 0,
+            };
+            Json::String(format_timestamp(seconds, nanos)?)
+        }
+        "google.protobuf.Duration" => {
+            let seconds = match get(message, 1) {
+                Some(Value::I64(seconds)) => *seconds,
+                _ => 0,
+            };
+            let nanos = match get(message, 2) {
+                Some(Value::I32(nanos)) => *nanos,
+                _ => 0,
+            };
+            Json::String(format_duration(seconds, nanos)?)
+        }
+        "google.protobuf.DoubleValue"
+        | "google.protobuf.FloatValue"
+        | "google.protobuf.Int64Value"
+        | "google.protobuf.UInt64Value"
+        | "google.protobuf.Int32Value"
+        | "google.protobuf.UInt32Value"
+        | "google.protobuf.BoolValue"
+        | "google.protobuf.StringValue"
+        | "google.protobuf.BytesValue" => {
+            // wrappers are written as their bare value
+            let kind = field_kind(descriptor, 1)?;
+            match get(message, 1) {
+                Some(value) => value_to_json(&kind, value)?,
+                None => value_to_json(&kind, &Value::default_for(&kind))?,
+            }
+        }
+        "google.protobuf.FieldMask" => {
+            let paths = match get(message, 1) {
+                Some(Value::List(paths)) => paths
+                    .iter()
+                    .filter_map(|path| match path {
+                        Value::String(path) => Some(snake_to_camel(path)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+                _ => Vec::new(),
+            };
+            Json::String(paths.join(","))
+        }
+        "google.protobuf.Struct" => {
+            let mut object = Map::new();
+            if let Some(Value::Map(entries)) = get(message, 1) {
+                for (key, value) in entries {
+                    if let (MapKey::String(key), Value::Message(value)) = (key, value) {
+                        object.insert(key.clone(), to_json(value)?);
+                    }
+                }
+            }
+            Json::Object(object)
+        }
+        "google.protobuf.ListValue" => {
+            let mut array = Vec::new();
+            if let Some(Value::List(values)) = get(message, 1) {
+                for value in values {
+                    if let Value::Message(value) = value {
+                        array.push(to_json(value)?);
+                    }
+                }
+            }
+            Json::Array(array)
+        }
+        "google.protobuf.Value" => match message.fields().next() {
+            Some((_, Value::F64(v))) => float_to_json(*v),
+            Some((_, Value::String(v))) => Json::String(v.clone()),
+            Some((_, Value::Bool(v))) => Json::Bool(*v),
+            Some((_, Value::Message(v))) => to_json(v)?,
+            _ => Json::Null,
+        },
+        "google.protobuf.Any" => any_to_json(message)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(json))
+}
+
+fn well_known_from_json(
+    descriptor: &MessageDescriptor,
+    json: &Json,
+) -> Result<Option<DynamicMessage>> {
+    let mut message = DynamicMessage::new(descriptor.clone());
+    match descriptor.full_name() {
+        "google.protobuf.Timestamp" => {
+            let (seconds, nanos) = json
+                .as_str()
+                .and_then(parse_timestamp)
+                .ok_or_else(|| anyhow!("expected an RFC 3339 timestamp, got {}", json))?;
+            set(&mut message, 1, Value::I64(seconds))?;
+            set(&mut message, 2, Value::I32(nanos))?;
+        }
+        "google.protobuf.Duration" => {
+            let (seconds, nanos) = json.as_str().and_then(parse_duration).ok_or_else(|| {
+                anyhow!("expected a duration in seconds like \"1.5s\", got {}", json)
+            })?;
+            set(&mut message, 1, Value::I64(seconds))?;
+            set(&mut message, 2, Value::I32(nanos))?;
+        }
+        "google.protobuf.DoubleValue"
+        | "google.protobuf.FloatValue"
+        | "google.protobuf.Int64Value"
+        | "google.protobuf.UInt64Value"
+        | "google.protobuf.Int32Value"
+        | "google.protobuf.UInt32Value"
+        | "google.protobuf.BoolValue"
+        | "google.protobuf.StringValue"
+        | "google.protobuf.BytesValue" => {
+            let value = value_from_json(&field_kind(descriptor, 1)?, json)?;
+            set(&mut message, 1, value)?;
+        }
+        "google.protobuf.FieldMask" => {
+            let paths = json
+                .as_str()
+                .ok_or_else(|| anyhow!("expected a comma-separated string of paths"))?;
+            let paths = paths
+                .split(',')
+                .filter(|path| !path.is_empty())
+                .map(|path| Value::String(camel_to_snake(path)))
+                .collect();
+            set(&mut message, 1, Value::List(paths))?;
+        }
+        "google.protobuf.Struct" => {
+            let object = json
+                .as_object()
+                .ok_or_else(|| anyhow!("expected an object"))?;
+            let value_kind = map_value_kind(&field_kind(descriptor, 1)?);
+            let mut entries = BTreeMap::new();
+            for (key, value) in object {
+                entries.insert(
+                    MapKey::String(key.clone()),
+                    value_from_json(&value_kind, value)?,
+                );
+            }
+            set(&mut message, 1, Value::Map(entries))?;
+        }
+        "google.protobuf.ListValue" => {
+            let array = json
+                .as_array()
+                .ok_or_else(|| anyhow!("expected an array"))?;
+            let kind = field_kind(descriptor, 1)?;
+            let values = array
+                .iter()
+                .map(|json| value_from_json(&kind, json))
+                .collect::<Result<_>>()?;
+            set(&mut message, 1, Value::List(values))?;
+        }
+        "google.protobuf.Value" => {
+            let (number, value) = match json {
+                Json::Null => (1, Value::EnumNumber(0)),
+                Json::Number(n) => (2, Value::F64(n.as_f64().unwrap_or_default())),
+                Json::String(s) => (3, Value::String(s.clone())),
+                Json::Bool(b) => (4, Value::Bool(*b)),
+                Json::Object(_) => (5, value_from_json(&field_kind(descriptor, 5)?, json)?),
+                Json::Array(_) => (6, value_from_json(&field_kind(descriptor, 6)?, json)?),
+            };
+            set(&mut message, number, value)?;
+        }
+        "google.protobuf.Any" => any_from_json(&mut message, json)?,
+        _ => return Ok(None),
+    }
+    Ok(Some(message))
+}
+
+// `Any` is written as its packed message with an extra "@type" member, or as
+// {"@type": ..., "value": ...} when the packed message has a special form
+fn any_to_json(message: &DynamicMessage) -> Result<Json> {
+    let type_url = match get(message, 1) {
+        Some(Value::String(type_url)) => type_url.clone(),
+        _ => return Ok(Json::Object(Map::new())),
+    };
+    let value = match get(message, 2) {
+        Some(Value::Bytes(value)) => value.clone(),
+        _ => Bytes::new(),
+    };
+
+    let descriptor = any_descriptor(message.descriptor(), &type_url)?;
+    let packed = DynamicMessage::decode(descriptor, value)?;
+
+    let mut object = Map::new();
+    object.insert("@type".to_string(), Json::String(type_url));
+    match well_known_to_json(&packed)? {
+        Some(json) => {
+            object.insert("value".to_string(), json);
+        }
+        None => {
+            if let Json::Object(fields) = to_json(&packed)? {
+                object.extend(fields);
+            }
+        }
+    }
+    Ok(Json::Object(object))
+}
+
+fn any_from_json(message: &mut DynamicMessage, json: &Json) -> Result<()> {
+    let object = json
+        .as_object()
+        .ok_or_else(|| anyhow!("expected an object"))?;
+    let type_url = match object.get("@type") {
+        Some(Json::String(type_url)) => type_url.clone(),
+        _ => bail!("expected an \"@type\" member"),
+    };
+
+    let descriptor = any_descriptor(message.descriptor(), &type_url)?;
+    let value = object.get("value").unwrap_or(&Json::Null);
+    let packed = match well_known_from_json(&descriptor, value)? {
+        Some(packed) => packed,
+        None => {
+            let mut fields = object.clone();
+            fields.remove("@type");
+            from_json(descriptor, &Json::Object(fields))?
+        }
+    };
+
+    set(message, 1, Value::String(type_url))?;
+    set(message, 2, Value::Bytes(packed.encode_to_bytes()))
+}
+
+fn any_descriptor(any: &MessageDescriptor, type_url: &str) -> Result<MessageDescriptor> {
+    let name = type_url.rsplit('/').next().unwrap_or(type_url);
+    any.pool()
+        .get_message_by_name(name)
+        .ok_or_else(|| anyhow!("cannot resolve the type of Any {:?}", type_url))
+}
+
+fn format_timestamp(seconds: i64, nanos: i32) -> Result<String> {
+    if !(MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&seconds)
+        || !(0..1_000_000_000).contains(&nanos)
+    {
+        bail!("timestamp out of range");
+    }
+
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    Ok(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        format_nanos(nanos as u32)
+    ))
+}
+
+fn parse_timestamp(s: &str) -> Option<(i64, i32)> {
+    let b = s.as_bytes();
+    if b.len() < 20
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || !matches!(b[10], b'T' | b't')
+        || b[13] != b':'
+        || b[16] != b':'
+    {
+        return None;
+    }
+
+    let year = parse_digits(&s[0..4])? as i64;
+    let month = parse_digits(&s[5..7])? as u32;
+    let day = parse_digits(&s[8..10])? as u32;
+    let hour = parse_digits(&s[11..13])? as i64;
+    let minute = parse_digits(&s[14..16])? as i64;
+    let second = parse_digits(&s[17..19])? as i64;
+    if year == 0
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let (nanos, rest) = parse_fraction(&s[19..])?;
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let minutes =
+                parse_digits(&rest[1..3])? as i64 * 60 + parse_digits(&rest[4..6])? as i64;
+            match rest.as_bytes()[0] {
+                b'+' => minutes * 60,
+                b'-' => -minutes * 60,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    let seconds =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    if !(MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&seconds) {
+        return None;
+    }
+    Some((seconds, nanos))
+}
+
+fn format_duration(seconds: i64, nanos: i32) -> Result<String> {
+    if seconds.abs() > MAX_DURATION_SECONDS
+        || nanos.abs() >= 1_000_000_000
+        || (seconds > 0 && nanos < 0)
+        || (seconds < 0 && nanos > 0)
+    {
+        bail!("duration out of range");
+    }
+
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    Ok(format!(
+        "{}{}{}s",
+        sign,
+        seconds.abs(),
+        format_nanos(nanos.unsigned_abs())
+    ))
+}
+
+fn parse_duration(s: &str) -> Option<(i64, i32)> {
+    let s = s.strip_suffix('s')?;
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+
+    let end = s.find('.').unwrap_or(s.len());
+    let seconds = parse_digits(&s[..end])? as i64;
+    let (nanos, rest) = parse_fraction(&s[end..])?;
+    if !rest.is_empty() || seconds > MAX_DURATION_SECONDS {
+        return None;
+    }
+
+    if negative {
+        Some((-seconds, -nanos))
+    } else {
+        Some((seconds, nanos))
+    }
+}
+
+// 0, 3, 6 or 9 fractional digits, whichever keeps the precision
+fn format_nanos(nanos: u32) -> String {
+    if nanos == 0 {
+        return String::new();
+    }
+
+    let mut fraction = format!(".{:09}", nanos);
+    while fraction.ends_with("000") {
+        fraction.truncate(fraction.len() - 3);
+    }
+    fraction
+}
+
+// an optional fraction of up to nine digits, and what follows it
+fn parse_fraction(s: &str) -> Option<(i32, &str)> {
+    let fraction = match s.strip_prefix('.') {
+        Some(fraction) => fraction,
+        None => return Some((0, s)),
+    };
+
+    let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let nanos = parse_digits(&fraction[..digits])? as i32 * 10i32.pow(9 - digits as u32);
+    Some((nanos, &fraction[digits..]))
+}
+
+fn parse_digits(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// days since 1970-01-01 to the proleptic Gregorian calendar and back
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * if month > 2 { month - 3 } else { month + 9 } + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn snake_to_camel(path: &str) -> String {
+    let mut camel = String::with_capacity(path.len());
+    let mut upper = false;
+    for c in path.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+fn camel_to_snake(path: &str) -> String {
+    let mut snake = String::with_capacity(path.len() + 4);
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
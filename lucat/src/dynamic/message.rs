@@ -0,0 +1,503 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use prost::encoding::{decode_key, decode_varint, encode_key, encode_varint, skip_field};
+use prost::encoding::{DecodeContext, WireType};
+use prost::DecodeError;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use super::descriptor::{FieldDescriptor, Kind, MessageDescriptor};
+
+// how deep messages may nest, the same limit as prost's `RECURSION_LIMIT`
+const RECURSION_LIMIT: u32 = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicMessage {
+    descriptor: MessageDescriptor,
+    fields: BTreeMap<u32, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Bytes),
+    EnumNumber(i32),
+    Message(DynamicMessage),
+    List(Vec<Value>),
+    Map(BTreeMap<MapKey, Value>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MapKey {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    String(String),
+}
+
+impl From<MapKey> for Value {
+    fn from(key: MapKey) -> Value {
+        match key {
+            MapKey::Bool(v) => Value::Bool(v),
+            MapKey::I32(v) => Value::I32(v),
+            MapKey::I64(v) => Value::I64(v),
+            MapKey::U32(v) => Value::U32(v),
+            MapKey::U64(v) => Value::U64(v),
+            MapKey::String(v) => Value::String(v),
+        }
+    }
+}
+
+impl Value {
+    fn into_map_key(self) -> Option<MapKey> {
+        match self {
+            Value::Bool(v) => Some(MapKey::Bool(v)),
+            Value::I32(v) => Some(MapKey::I32(v)),
+            Value::I64(v) => Some(MapKey::I64(v)),
+            Value::U32(v) => Some(MapKey::U32(v)),
+            Value::U64(v) => Some(MapKey::U64(v)),
+            Value::String(v) => Some(MapKey::String(v)),
+            _ => None,
+        }
+    }
+
+    pub fn default_for(kind: &Kind) -> Value {
+        match kind {
+            Kind::Double => Value::F64(0.0),
+            Kind::Float => Value::F32(0.0),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(0),
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(0),
+            Kind::Uint32 | Kind::Fixed32 => Value::U32(0),
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(0),
+            Kind::Bool => Value::Bool(false),
+            Kind::String => Value::String(String::new()),
+            Kind::Bytes => Value::Bytes(Bytes::new()),
+            Kind::Enum(_) => Value::EnumNumber(0),
+            Kind::Message(message) => Value::Message(DynamicMessage::new(message.clone())),
+        }
+    }
+}
+
+impl DynamicMessage {
+    pub fn new(descriptor: MessageDescriptor) -> Self {
+        DynamicMessage {
+            descriptor,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn decode(descriptor: MessageDescriptor, mut buf: impl Buf) -> Result<Self, DecodeError> {
+        let mut message = DynamicMessage::new(descriptor);
+        message.merge(&mut buf, RECURSION_LIMIT)?;
+        Ok(message)
+    }
+
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.descriptor
+    }
+
+    pub fn get_field(&self, field: &FieldDescriptor) -> Option<&Value> {
+        self.fields.get(&field.number())
+    }
+
+    // Setting a member of a oneof clears the others.
+    pub fn set_field(&mut self, field: &FieldDescriptor, value: Value) -> Result<(), InvalidFieldValue> {
+        if !field_accepts(field, &value) {
+            return Err(InvalidFieldValue {
+                field: field.name().to_string(),
+            });
+        }
+        self.clear_oneof(field);
+        self.fields.insert(field.number(), value);
+        Ok(())
+    }
+
+    fn clear_oneof(&mut self, field: &FieldDescriptor) {
+        let oneof = match field.oneof_name() {
+            Some(oneof) => oneof,
+            None => return,
+        };
+        for other in self.descriptor.fields() {
+            if other.number() != field.number() && other.oneof_name() == Some(oneof) {
+                self.fields.remove(&other.number());
+            }
+        }
+    }
+
+    // the fields that are set, in field number order
+    pub fn fields(&self) -> impl Iterator<Item = (FieldDescriptor, &Value)> + '_ {
+        self.fields.iter().filter_map(move |(number, value)| {
+            self.descriptor.get_field(*number).map(|field| (field, value))
+        })
+    }
+
+    pub fn encode_to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        self.encode_raw(&mut buf);
+        buf.freeze()
+    }
+
+    fn encode_raw(&self, buf: &mut BytesMut) {
+        for (field, value) in self.fields() {
+            let kind = field.kind();
+            let number = field.number();
+
+            match value {
+                Value::List(values) if field.is_packed() => {
+                    if values.is_empty() {
+                        continue;
+                    }
+                    let mut packed = BytesMut::new();
+                    for value in values {
+                        encode_scalar(&kind, value, &mut packed);
+                    }
+                    encode_key(number, WireType::LengthDelimited, buf);
+                    encode_varint(packed.len() as u64, buf);
+                    buf.put(packed);
+                }
+                Value::List(values) => {
+                    for value in values {
+                        encode_field(number, &kind, value, buf);
+                    }
+                }
+                Value::Map(entries) => {
+                    let entry = match &kind {
+                        Kind::Message(entry) => entry,
+                        _ => continue,
+                    };
+                    let (key_field, value_field) = match (entry.get_field(1), entry.get_field(2)) {
+                        (Some(key), Some(value)) => (key, value),
+                        _ => continue,
+                    };
+
+                    for (key, value) in entries {
+                        // both were checked against the entry when the map was set
+                        let mut message = DynamicMessage::new(entry.clone());
+                        message.fields.insert(key_field.number(), key.clone().into());
+                        message.fields.insert(value_field.number(), value.clone());
+                        encode_field(number, &kind, &Value::Message(message), buf);
+                    }
+                }
+                value => encode_field(number, &kind, value, buf),
+            }
+        }
+    }
+
+    // Merges the length-delimited message at the front of `buf` into `self`,
+    // `depth` being how many more levels of nesting are allowed.
+    fn merge_nested(&mut self, buf: &mut impl Buf, depth: u32) -> Result<(), DecodeError> {
+        if depth == 0 {
+            return Err(DecodeError::new("recursion limit reached"));
+        }
+        let mut data = length_delimited(buf)?;
+        self.merge(&mut data, depth - 1)
+    }
+
+    fn merge(&mut self, buf: &mut impl Buf, depth: u32) -> Result<(), DecodeError> {
+        while buf.has_remaining() {
+            let (number, wire_type) = decode_key(buf)?;
+            let field = match self.descriptor.get_field(number) {
+                Some(field) => field,
+                None => {
+                    skip_field(wire_type, number, buf, DecodeContext::default())?;
+                    continue;
+                }
+            };
+            let kind = field.kind();
+
+            if field.is_map() {
+                let entry = match decode_field(&kind, wire_type, buf, depth)? {
+                    Value::Message(entry) => entry,
+                    _ => return Err(DecodeError::new("invalid map entry")),
+                };
+                let (key_field, value_field) = match (
+                    entry.descriptor.get_field(1),
+                    entry.descriptor.get_field(2),
+                ) {
+                    (Some(key), Some(value)) => (key, value),
+                    _ => return Err(DecodeError::new("invalid map entry")),
+                };
+
+                let key = match entry.get_field(&key_field) {
+                    Some(key) => key.clone(),
+                    None => Value::default_for(&key_field.kind()),
+                };
+                let value = match entry.get_field(&value_field) {
+                    Some(value) => value.clone(),
+                    None => Value::default_for(&value_field.kind()),
+                };
+                let key = key
+                    .into_map_key()
+                    .ok_or_else(|| DecodeError::new("invalid map key"))?;
+
+                match self.fields.entry(number).or_insert_with(|| Value::Map(BTreeMap::new())) {
+                    Value::Map(entries) => {
+                        entries.insert(key, value);
+                    }
+                    _ => unreachable!(),
+                }
+            } else if field.is_list() {
+                let values = match self.fields.entry(number).or_insert_with(|| Value::List(Vec::new())) {
+                    Value::List(values) => values,
+                    _ => unreachable!(),
+                };
+
+                // parsers have to accept packed and unpacked encodings alike
+                if wire_type == WireType::LengthDelimited && is_scalar(&kind) {
+                    let len = decode_varint(buf)? as usize;
+                    if len > buf.remaining() {
+                        return Err(DecodeError::new("buffer underflow"));
+                    }
+                    let mut packed = buf.copy_to_bytes(len);
+                    while packed.has_remaining() {
+                        values.push(decode_scalar(&kind, scalar_wire_type(&kind), &mut packed)?);
+                    }
+                } else {
+                    values.push(decode_field(&kind, wire_type, buf, depth)?);
+                }
+            } else {
+                match self.fields.get_mut(&number) {
+                    // repeated occurrences of an embedded message are merged
+                    // into it the way protobuf merges any two messages
+                    Some(Value::Message(existing)) if wire_type == WireType::LengthDelimited => {
+                        existing.merge_nested(buf, depth)?;
+                    }
+                    _ => {
+                        let value = decode_field(&kind, wire_type, buf, depth)?;
+                        self.clear_oneof(&field);
+                        self.fields.insert(number, value);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// A value that doesn't fit the type of the field it was set on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidFieldValue {
+    field: String,
+}
+
+impl fmt::Display for InvalidFieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value does not match the type of field {}", self.field)
+    }
+}
+
+impl Error for InvalidFieldValue {}
+
+fn field_accepts(field: &FieldDescriptor, value: &Value) -> bool {
+    let kind = field.kind();
+    if field.is_map() {
+        let entry = match &kind {
+            Kind::Message(entry) => entry,
+            _ => return false,
+        };
+        let (key_kind, value_kind) = match (entry.get_field(1), entry.get_field(2)) {
+            (Some(key), Some(value)) => (key.kind(), value.kind()),
+            _ => return false,
+        };
+        match value {
+            Value::Map(entries) => entries.iter().all(|(key, value)| {
+                key_accepts(&key_kind, key) && kind_accepts(&value_kind, value)
+            }),
+            _ => false,
+        }
+    } else if field.is_list() {
+        match value {
+            Value::List(values) => values.iter().all(|value| kind_accepts(&kind, value)),
+            _ => false,
+        }
+    } else {
+        kind_accepts(&kind, value)
+    }
+}
+
+fn kind_accepts(kind: &Kind, value: &Value) -> bool {
+    match (kind, value) {
+        (Kind::Message(descriptor), Value::Message(message)) => message.descriptor() == descriptor,
+        (Kind::Double, Value::F64(_))
+        | (Kind::Float, Value::F32(_))
+        | (Kind::Int32, Value::I32(_))
+        | (Kind::Sint32, Value::I32(_))
+        | (Kind::Sfixed32, Value::I32(_))
+        | (Kind::Int64, Value::I64(_))
+        | (Kind::Sint64, Value::I64(_))
+        | (Kind::Sfixed64, Value::I64(_))
+        | (Kind::Uint32, Value::U32(_))
+        | (Kind::Fixed32, Value::U32(_))
+        | (Kind::Uint64, Value::U64(_))
+        | (Kind::Fixed64, Value::U64(_))
+        | (Kind::Bool, Value::Bool(_))
+        | (Kind::String, Value::String(_))
+        | (Kind::Bytes, Value::Bytes(_))
+        | (Kind::Enum(_), Value::EnumNumber(_)) => true,
+        _ => false,
+    }
+}
+
+fn key_accepts(kind: &Kind, key: &MapKey) -> bool {
+    matches!(
+        (kind, key),
+        (Kind::Bool, MapKey::Bool(_))
+            | (Kind::Int32, MapKey::I32(_))
+            | (Kind::Sint32, MapKey::I32(_))
+            | (Kind::Sfixed32, MapKey::I32(_))
+            | (Kind::Int64, MapKey::I64(_))
+            | (Kind::Sint64, MapKey::I64(_))
+            | (Kind::Sfixed64, MapKey::I64(_))
+            | (Kind::Uint32, MapKey::U32(_))
+            | (Kind::Fixed32, MapKey::U32(_))
+            | (Kind::Uint64, MapKey::U64(_))
+            | (Kind::Fixed64, MapKey::U64(_))
+            | (Kind::String, MapKey::String(_))
+    )
+}
+
+fn is_scalar(kind: &Kind) -> bool {
+    !matches!(kind, Kind::String | Kind::Bytes | Kind::Message(_))
+}
+
+fn scalar_wire_type(kind: &Kind) -> WireType {
+    match kind {
+        Kind::Double | Kind::Fixed64 | Kind::Sfixed64 => WireType::SixtyFourBit,
+        Kind::Float | Kind::Fixed32 | Kind::Sfixed32 => WireType::ThirtyTwoBit,
+        Kind::String | Kind::Bytes | Kind::Message(_) => WireType::LengthDelimited,
+        _ => WireType::Varint,
+    }
+}
+
+fn encode_field(number: u32, kind: &Kind, value: &Value, buf: &mut BytesMut) {
+    match value {
+        Value::String(v) => {
+            encode_key(number, WireType::LengthDelimited, buf);
+            encode_varint(v.len() as u64, buf);
+            buf.put_slice(v.as_bytes());
+        }
+        Value::Bytes(v) => {
+            encode_key(number, WireType::LengthDelimited, buf);
+            encode_varint(v.len() as u64, buf);
+            buf.put_slice(v);
+        }
+        Value::Message(message) => {
+            let mut nested = BytesMut::new();
+            message.encode_raw(&mut nested);
+            encode_key(number, WireType::LengthDelimited, buf);
+            encode_varint(nested.len() as u64, buf);
+            buf.put(nested);
+        }
+        value => {
+            encode_key(number, scalar_wire_type(kind), buf);
+            encode_scalar(kind, value, buf);
+        }
+    }
+}
+
+fn encode_scalar(kind: &Kind, value: &Value, buf: &mut BytesMut) {
+    match (kind, value) {
+        (Kind::Double, Value::F64(v)) => buf.put_f64_le(*v),
+        (Kind::Float, Value::F32(v)) => buf.put_f32_le(*v),
+        (Kind::Fixed64, Value::U64(v)) => buf.put_u64_le(*v),
+        (Kind::Sfixed64, Value::I64(v)) => buf.put_i64_le(*v),
+        (Kind::Fixed32, Value::U32(v)) => buf.put_u32_le(*v),
+        (Kind::Sfixed32, Value::I32(v)) => buf.put_i32_le(*v),
+        (Kind::Sint32, Value::I32(v)) => encode_varint(((v << 1) ^ (v >> 31)) as u32 as u64, buf),
+        (Kind::Sint64, Value::I64(v)) => encode_varint(((v << 1) ^ (v >> 63)) as u64, buf),
+        // negative int32s are sign extended to ten bytes like int64s
+        (Kind::Int32, Value::I32(v)) | (Kind::Enum(_), Value::EnumNumber(v)) => {
+            encode_varint(*v as i64 as u64, buf)
+        }
+        (Kind::Int64, Value::I64(v)) => encode_varint(*v as u64, buf),
+        (Kind::Uint32, Value::U32(v)) => encode_varint(*v as u64, buf),
+        (Kind::Uint64, Value::U64(v)) => encode_varint(*v, buf),
+        (Kind::Bool, Value::Bool(v)) => encode_varint(*v as u64, buf),
+        _ => unreachable!("set_field only takes values that match their field"),
+    }
+}
+
+fn length_delimited(buf: &mut impl Buf) -> Result<Bytes, DecodeError> {
+    let len = decode_varint(buf)? as usize;
+    if len > buf.remaining() {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(buf.copy_to_bytes(len))
+}
+
+fn decode_field(
+    kind: &Kind,
+    wire_type: WireType,
+    buf: &mut impl Buf,
+    depth: u32,
+) -> Result<Value, DecodeError> {
+    if wire_type != scalar_wire_type(kind) {
+        return Err(DecodeError::new(format!(
+            "invalid wire type: {:?} (expected {:?})",
+            wire_type,
+            scalar_wire_type(kind)
+        )));
+    }
+
+    match kind {
+        Kind::String => String::from_utf8(length_delimited(buf)?.to_vec())
+            .map(Value::String)
+            .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded")),
+        Kind::Bytes => length_delimited(buf).map(Value::Bytes),
+        Kind::Message(descriptor) => {
+            let mut message = DynamicMessage::new(descriptor.clone());
+            message.merge_nested(buf, depth)?;
+            Ok(Value::Message(message))
+        }
+        kind => decode_scalar(kind, wire_type, buf),
+    }
+}
+
+fn decode_scalar(kind: &Kind, wire_type: WireType, buf: &mut impl Buf) -> Result<Value, DecodeError> {
+    let needed = match wire_type {
+        WireType::SixtyFourBit => 8,
+        WireType::ThirtyTwoBit => 4,
+        _ => 0,
+    };
+    if buf.remaining() < needed {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+
+    let value = match kind {
+        Kind::Double => Value::F64(buf.get_f64_le()),
+        Kind::Float => Value::F32(buf.get_f32_le()),
+        Kind::Fixed64 => Value::U64(buf.get_u64_le()),
+        Kind::Sfixed64 => Value::I64(buf.get_i64_le()),
+        Kind::Fixed32 => Value::U32(buf.get_u32_le()),
+        Kind::Sfixed32 => Value::I32(buf.get_i32_le()),
+        Kind::Int32 => Value::I32(decode_varint(buf)? as i32),
+        Kind::Int64 => Value::I64(decode_varint(buf)? as i64),
+        Kind::Uint32 => Value::U32(decode_varint(buf)? as u32),
+        Kind::Uint64 => Value::U64(decode_varint(buf)?),
+        Kind::Sint32 => {
+            let v = decode_varint(buf)? as u32;
+            Value::I32(((v >> 1) as i32) ^ -((v & 1) as i32))
+        }
+        Kind::Sint64 => {
+            let v = decode_varint(buf)?;
+            Value::I64(((v >> 1) as i64) ^ -((v & 1) as i64))
+        }
+        Kind::Bool => Value::Bool(decode_varint(buf)? != 0),
+        Kind::Enum(_) => Value::EnumNumber(decode_varint(buf)? as i32),
+        Kind::String | Kind::Bytes | Kind::Message(_) => {
+            return Err(DecodeError::new("not a scalar field"))
+        }
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,16 @@
+mod descriptor;
+mod json;
+mod message;
+
+pub use self::descriptor::{
+    DescriptorPool, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor, MethodDescriptor,
+    ServiceDescriptor,
+};
+pub use self::json::{from_json, to_json};
+pub use self::message::{DynamicMessage, InvalidFieldValue, MapKey, Value};
+
+// A generated message that knows its own descriptor. lucat-build implements
+// it for the messages of services that use a codec other than ProstCodec.
+pub trait ReflectMessage: prost::Message + Default {
+    fn descriptor() -> MessageDescriptor;
+}
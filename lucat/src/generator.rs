@@ -7,4 +7,6 @@ pub use std::sync::Arc;
 
 pub use crate::runtime::{Service, InstantService};
 
+pub use cynthia::runtime::stream::Stream;
+
 pub type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -0,0 +1,132 @@
+use cynthia::platform::event::Event;
+use cynthia::runtime::stream::{self, Stream};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::transport::NamedService;
+use crate::{Request, Response, Status};
+
+pub mod pb {
+    crate::include_proto!("grpc.health.v1");
+}
+
+pub use pb::health_check_response::ServingStatus;
+
+use pb::health_server::{Health, HealthServer};
+use pb::{HealthCheckRequest, HealthCheckResponse};
+
+pub fn health_reporter() -> (HealthReporter, HealthServer<HealthService>) {
+    let shared = Arc::new(Shared {
+        statuses: Mutex::new(HashMap::new()),
+        event: Event::new(),
+    });
+
+    let reporter = HealthReporter {
+        shared: shared.clone(),
+    };
+    // the empty service name stands for the server as a whole
+    reporter.set_service_status("", ServingStatus::Serving);
+
+    (reporter, HealthServer::new(HealthService { shared }))
+}
+
+struct Shared {
+    statuses: Mutex<HashMap<String, ServingStatus>>,
+    event: Event,
+}
+
+impl Shared {
+    fn statuses(&self) -> MutexGuard<'_, HashMap<String, ServingStatus>> {
+        self.statuses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn status(&self, service_name: &str) -> Option<ServingStatus> {
+        self.statuses().get(service_name).copied()
+    }
+}
+
+#[derive(Clone)]
+pub struct HealthReporter {
+    shared: Arc<Shared>,
+}
+
+impl HealthReporter {
+    pub fn set_serving<S: NamedService>(&self) {
+        self.set_service_status(S::NAME, ServingStatus::Serving);
+    }
+
+    pub fn set_not_serving<S: NamedService>(&self) {
+        self.set_service_status(S::NAME, ServingStatus::NotServing);
+    }
+
+    pub fn set_service_status(&self, service_name: impl Into<String>, status: ServingStatus) {
+        let previous = self.shared.statuses().insert(service_name.into(), status);
+        if previous != Some(status) {
+            self.shared.event.notify(usize::MAX);
+        }
+    }
+
+    pub fn clear_service_status(&self, service_name: &str) {
+        if self.shared.statuses().remove(service_name).is_some() {
+            self.shared.event.notify(usize::MAX);
+        }
+    }
+}
+
+pub struct HealthService {
+    shared: Arc<Shared>,
+}
+
+#[crate::async_trait]
+impl Health for HealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service_name = request.into_inner().service;
+        match self.shared.status(&service_name) {
+            Some(status) => Ok(Response::new(HealthCheckResponse {
+                status: status as i32,
+            })),
+            None => Err(Status::not_found(format!(
+                "service {:?} is not registered",
+                service_name
+            ))),
+        }
+    }
+
+    type WatchStream =
+        Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send + Sync + 'static>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service_name = request.into_inner().service;
+        let state = (self.shared.clone(), service_name, None);
+
+        let stream = stream::unfold(state, |(shared, service_name, last)| async move {
+            loop {
+                // listen before reading so an update in between is not lost
+                let listener = shared.event.listen();
+                let status = shared
+                    .status(&service_name)
+                    .unwrap_or(ServingStatus::ServiceUnknown);
+
+                if last != Some(status) {
+                    let response = HealthCheckResponse {
+                        status: status as i32,
+                    };
+                    return Some((Ok(response), (shared, service_name, Some(status))));
+                }
+
+                listener.await;
+            }
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
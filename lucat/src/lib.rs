@@ -1,4 +1,5 @@
 pub mod codec;
+pub mod dynamic;
 pub mod common;
 pub mod metadata;
 pub mod transport;
@@ -6,6 +7,13 @@ pub mod controller;
 pub mod runtime;
 pub mod generator;
 pub mod macros;
+pub mod health;
+pub mod reflection;
+pub mod proxy;
+pub mod status_details;
+
+// lets the code generated for the bundled protos name this crate as `lucat`
+extern crate self as lucat;
 
 pub use async_trait::async_trait;
 
@@ -13,10 +21,12 @@ pub use common::status::{Code, Status};
 
 pub use common::response::Response;
 
-pub use common::request::Request;
+pub use common::request::{IntoRequest, IntoStreamingRequest, Request};
 
 pub use common::Body;
 
+pub use codec::Streaming;
+
 pub use controller::server;
 pub use controller::client;
 
@@ -4,3 +4,10 @@ macro_rules! include_proto {
         include!(concat!(env!("OUT_DIR"), concat!("/", $package, ".rs")));
     };
 }
+
+#[macro_export]
+macro_rules! include_file_descriptor_set {
+    ($package: tt) => {
+        include_bytes!(concat!(env!("OUT_DIR"), concat!("/", $package, ".bin")))
+    };
+}
@@ -26,17 +26,11 @@ pub type BinaryMetadataKey = MetadataKey<Binary>;
 impl<VE: ValueEncoding> MetadataKey<VE> {
     pub fn from_bytes(src: &[u8]) -> Result<Self, InvalidMetadataKey> {
         match HeaderName::from_bytes(src) {
-            Ok(name) => {
-                if !VE::is_valid_key(name.as_str()) {
-                    panic!("invalid metadata key")
-                }
-
-                Ok(MetadataKey {
-                    inner: name,
-                    phantom: PhantomData,
-                })
-            }
-            Err(_) => Err(InvalidMetadataKey::new()),
+            Ok(name) if VE::is_valid_key(name.as_str()) => Ok(MetadataKey {
+                inner: name,
+                phantom: PhantomData,
+            }),
+            _ => Err(InvalidMetadataKey::new()),
         }
     }
 
@@ -0,0 +1,208 @@
+use bytes::Bytes;
+use cynthia::io::Timer;
+use cynthia::runtime::future;
+use cynthia::runtime::stream::{self, Stream, StreamExt};
+use http::uri::PathAndQuery;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use crate::codec::{self, BytesDecoder, BytesEncoder, ContentType, MessageSizes, Streaming};
+use crate::codec::DEFAULT_MAX_ENCODING_MESSAGE_SIZE;
+use crate::metadata::{MetadataValue, GRPC_TIMEOUT_HEADER};
+use crate::runtime::BoxFuture;
+use crate::transport::server::FallbackService;
+use crate::transport::Endpoint;
+use crate::{Body, Code, Request, Response, Status};
+
+pub fn configure() -> Builder {
+    Builder { routes: Vec::new() }
+}
+
+pub struct Builder {
+    routes: Vec<(String, Endpoint)>,
+}
+
+impl Builder {
+    // Forwards the calls whose method path starts with `prefix`, like
+    // "/echo.Echo/" for a whole service or "/" for everything; the first
+    // matching route wins.
+    pub fn route(mut self, prefix: impl Into<String>, upstream: Endpoint) -> Self {
+        self.routes.push((prefix.into(), upstream));
+        self
+    }
+
+    pub fn build(self) -> Proxy {
+        Proxy {
+            routes: Arc::new(self.routes),
+        }
+    }
+}
+
+// Forwards calls to upstream servers without decoding their messages. It is
+// installed as the fallback of a server, see `Server::fallback` and
+// `Router::fallback`.
+#[derive(Clone)]
+pub struct Proxy {
+    routes: Arc<Vec<(String, Endpoint)>>,
+}
+
+impl Proxy {
+    fn upstream(&self, path: &str) -> Option<Endpoint> {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map(|(_, upstream)| upstream.clone())
+    }
+}
+
+type Frames = Pin<Box<dyn Stream<Item = Result<Bytes, Status>> + Send>>;
+
+impl FallbackService for Proxy {
+    type ResponseStream = Frames;
+    type Future = BoxFuture<Response<Frames>, Status>;
+
+    fn call(&mut self, path: PathAndQuery, request: Request<Streaming<Bytes>>) -> Self::Future {
+        let upstream = self.upstream(path.path());
+        Box::pin(async move {
+            let upstream = upstream
+                .ok_or_else(|| Status::unimplemented(format!("no upstream for {}", path.path())))?;
+            forward(upstream, path, request).await
+        })
+    }
+}
+
+async fn forward(
+    mut upstream: Endpoint,
+    path: PathAndQuery,
+    request: Request<Streaming<Bytes>>,
+) -> Result<Response<Frames>, Status> {
+    let deadline = request
+        .metadata()
+        .get(GRPC_TIMEOUT_HEADER)
+        .and_then(|timeout| timeout.to_str().ok())
+        .and_then(parse_timeout)
+        .map(|timeout| Instant::now() + timeout);
+    let content_type = request.extensions().get::<ContentType>().cloned().unwrap_or_default();
+
+    let mut request = request.map(|frames| {
+        Body::stream(codec::encode(BytesEncoder, frames, DEFAULT_MAX_ENCODING_MESSAGE_SIZE))
+    });
+    request.extensions_mut().insert(path);
+    request.extensions_mut().insert(content_type.clone());
+
+    // the upstream gets what is left of the caller's deadline
+    if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if let Ok(timeout) = MetadataValue::from_str(&encode_timeout(remaining)) {
+            request.metadata_mut().insert(GRPC_TIMEOUT_HEADER, timeout);
+        }
+    }
+
+    let call = async { upstream.request(request).await.map_err(Status::from_error) };
+    let response = match deadline {
+        Some(deadline) => future::or(call, expire(deadline)).await?,
+        None => call.await?,
+    };
+
+    let max = MessageSizes::max_decoding(None, response.extensions());
+    let mut response = response.map(|body| {
+        let frames = with_trailers(Streaming::new(body, BytesDecoder).max_message_size(max));
+        match deadline {
+            Some(deadline) => until(deadline, frames),
+            None => frames.boxed(),
+        }
+    });
+    response.extensions_mut().insert(content_type);
+
+    Ok(response)
+}
+
+// Ends `frames` with the status the upstream ended the call with, so that its
+// trailing metadata reaches the caller even when the call succeeded. Failed
+// calls carry their trailers in the status already.
+fn with_trailers(mut frames: Streaming<Bytes>) -> Frames {
+    let mut done = false;
+    stream::poll_fn(move |cx| {
+        if done {
+            return Poll::Ready(None);
+        }
+        match Pin::new(&mut frames).poll_next(cx) {
+            Poll::Ready(None) => {
+                done = true;
+                let trailers = frames.trailers().cloned().unwrap_or_default();
+                Poll::Ready(Some(Err(Status::with_metadata(Code::Ok, "", trailers))))
+            }
+            polled => polled,
+        }
+    })
+    .boxed()
+}
+
+async fn expire<T>(deadline: Instant) -> Result<T, Status> {
+    Timer::at(deadline).await;
+    Err(Status::deadline_exceeded("deadline exceeded"))
+}
+
+// Ends `frames` with `DeadlineExceeded` once the deadline has passed.
+fn until<S>(deadline: Instant, mut frames: S) -> Frames
+where
+    S: Stream<Item = Result<Bytes, Status>> + Unpin + Send + 'static,
+{
+    let mut timer = Some(Timer::at(deadline));
+    stream::poll_fn(move |cx| {
+        let expired = match timer.as_mut() {
+            Some(timer) => Pin::new(timer).poll(cx).is_ready(),
+            None => return Poll::Ready(None),
+        };
+        if expired {
+            timer = None;
+            return Poll::Ready(Some(Err(Status::deadline_exceeded("deadline exceeded"))));
+        }
+        Pin::new(&mut frames).poll_next(cx)
+    })
+    .boxed()
+}
+
+// `grpc-timeout` is at most eight digits followed by a unit.
+fn parse_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+    Some(timeout)
+}
+
+fn encode_timeout(timeout: Duration) -> String {
+    const MAX: u128 = 99_999_999;
+
+    let nanos = timeout.as_nanos();
+    let units = [
+        (1, "n"),
+        (1_000, "u"),
+        (1_000_000, "m"),
+        (1_000_000_000, "S"),
+        (60 * 1_000_000_000, "M"),
+        (60 * 60 * 1_000_000_000, "H"),
+    ];
+    for (size, unit) in units.iter() {
+        // rounded up, so that a few nanoseconds left are not sent as none
+        let amount = nanos.div_ceil(*size);
+        if amount <= MAX {
+            return format!("{}{}", amount, unit);
+        }
+    }
+    format!("{}H", MAX)
+}
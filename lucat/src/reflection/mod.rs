@@ -0,0 +1,280 @@
+use cynthia::runtime::stream::{Stream, StreamExt};
+use prost::Message;
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error as StdError;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::{Request, Response, Status, Streaming};
+
+pub mod pb {
+    crate::include_proto!("grpc.reflection.v1alpha");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        crate::include_file_descriptor_set!("reflection_descriptor");
+}
+
+use pb::server_reflection_request::MessageRequest;
+use pb::server_reflection_response::MessageResponse;
+use pb::server_reflection_server::{ServerReflection, ServerReflectionServer};
+use pb::{
+    ErrorResponse, FileDescriptorResponse, ListServiceResponse, ServerReflectionRequest,
+    ServerReflectionResponse, ServiceResponse,
+};
+
+pub fn configure() -> Builder {
+    Builder {
+        encoded_sets: Vec::new(),
+        sets: Vec::new(),
+        include_reflection_service: true,
+    }
+}
+
+pub struct Builder {
+    encoded_sets: Vec<&'static [u8]>,
+    sets: Vec<FileDescriptorSet>,
+    include_reflection_service: bool,
+}
+
+impl Builder {
+    pub fn register_encoded_file_descriptor_set(mut self, encoded: &'static [u8]) -> Self {
+        self.encoded_sets.push(encoded);
+        self
+    }
+
+    pub fn register_file_descriptor_set(mut self, set: FileDescriptorSet) -> Self {
+        self.sets.push(set);
+        self
+    }
+
+    pub fn include_reflection_service(mut self, include: bool) -> Self {
+        self.include_reflection_service = include;
+        self
+    }
+
+    pub fn build(self) -> Result<ServerReflectionServer<ReflectionService>, Error> {
+        let mut index = Index::default();
+
+        if self.include_reflection_service {
+            index.add_set(FileDescriptorSet::decode(pb::FILE_DESCRIPTOR_SET)?)?;
+        }
+        for encoded in self.encoded_sets {
+            index.add_set(FileDescriptorSet::decode(encoded)?)?;
+        }
+        for set in self.sets {
+            index.add_set(set)?;
+        }
+
+        Ok(ServerReflectionServer::new(ReflectionService {
+            index: Arc::new(index),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    DecodeError(prost::DecodeError),
+    InvalidFileDescriptorSet(String),
+}
+
+impl From<prost::DecodeError> for Error {
+    fn from(error: prost::DecodeError) -> Self {
+        Error::DecodeError(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DecodeError(_) => f.write_str("failed to decode file descriptor set"),
+            Error::InvalidFileDescriptorSet(message) => {
+                write!(f, "invalid file descriptor set: {}", message)
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::DecodeError(error) => Some(error),
+            Error::InvalidFileDescriptorSet(_) => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Index {
+    // encoded once up front, that is the form the protocol hands them out in
+    files: HashMap<String, (FileDescriptorProto, Vec<u8>)>,
+    symbols: HashMap<String, String>,
+    services: Vec<String>,
+}
+
+impl Index {
+    fn add_set(&mut self, set: FileDescriptorSet) -> Result<(), Error> {
+        for file in set.file {
+            self.add_file(file)?;
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, file: FileDescriptorProto) -> Result<(), Error> {
+        let filename = match file.name.as_ref() {
+            Some(name) => name.clone(),
+            None => {
+                return Err(Error::InvalidFileDescriptorSet(
+                    "file descriptor without a name".to_string(),
+                ))
+            }
+        };
+        if self.files.contains_key(&filename) {
+            return Ok(());
+        }
+
+        let prefix = match file.package() {
+            "" => String::new(),
+            package => format!("{}.", package),
+        };
+
+        for message in file.message_type.iter() {
+            self.add_message(&prefix, message, &filename);
+        }
+        for enumeration in file.enum_type.iter() {
+            self.add_enum(&prefix, enumeration, &filename);
+        }
+        for service in file.service.iter() {
+            let name = format!("{}{}", prefix, service.name());
+            for method in service.method.iter() {
+                self.add_symbol(format!("{}.{}", name, method.name()), &filename);
+            }
+            self.add_symbol(name.clone(), &filename);
+            self.services.push(name);
+        }
+
+        let encoded = file.encode_to_vec();
+        self.files.insert(filename, (file, encoded));
+        Ok(())
+    }
+
+    fn add_message(&mut self, prefix: &str, message: &DescriptorProto, filename: &str) {
+        let name = format!("{}{}", prefix, message.name());
+        let nested = format!("{}.", name);
+
+        for field in message.field.iter() {
+            self.add_symbol(format!("{}{}", nested, field.name()), filename);
+        }
+        for message in message.nested_type.iter() {
+            self.add_message(&nested, message, filename);
+        }
+        for enumeration in message.enum_type.iter() {
+            self.add_enum(&nested, enumeration, filename);
+        }
+        self.add_symbol(name, filename);
+    }
+
+    fn add_enum(&mut self, prefix: &str, enumeration: &EnumDescriptorProto, filename: &str) {
+        self.add_symbol(format!("{}{}", prefix, enumeration.name()), filename);
+    }
+
+    fn add_symbol(&mut self, symbol: String, filename: &str) {
+        self.symbols.entry(symbol).or_insert_with(|| filename.to_string());
+    }
+
+    // the requested file first, followed by whatever it imports, so a client
+    // can resolve every type it refers to from one response
+    fn file_with_dependencies(&self, filename: &str) -> Result<Vec<Vec<u8>>, Status> {
+        if !self.files.contains_key(filename) {
+            return Err(Status::not_found(format!("file {:?} not found", filename)));
+        }
+
+        let mut encoded = Vec::new();
+        let mut seen = HashSet::new();
+        let mut pending = VecDeque::new();
+        pending.push_back(filename);
+
+        while let Some(filename) = pending.pop_front() {
+            if !seen.insert(filename) {
+                continue;
+            }
+            if let Some((file, bytes)) = self.files.get(filename) {
+                encoded.push(bytes.clone());
+                pending.extend(file.dependency.iter().map(String::as_str));
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    fn respond(&self, request: ServerReflectionRequest) -> ServerReflectionResponse {
+        let response = match request.message_request.as_ref() {
+            Some(MessageRequest::FileByFilename(filename)) => self
+                .file_with_dependencies(filename)
+                .map(file_descriptor_response),
+            Some(MessageRequest::FileContainingSymbol(symbol)) => match self.symbols.get(symbol) {
+                Some(filename) => self
+                    .file_with_dependencies(filename)
+                    .map(file_descriptor_response),
+                None => Err(Status::not_found(format!("symbol {:?} not found", symbol))),
+            },
+            Some(MessageRequest::ListServices(_)) => {
+                let service = self
+                    .services
+                    .iter()
+                    .map(|name| ServiceResponse { name: name.clone() })
+                    .collect();
+                Ok(MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service,
+                }))
+            }
+            Some(MessageRequest::FileContainingExtension(_))
+            | Some(MessageRequest::AllExtensionNumbersOfType(_)) => {
+                Err(Status::unimplemented("extensions are not supported"))
+            }
+            None => Err(Status::invalid_argument("message_request is not set")),
+        };
+
+        let response = response.unwrap_or_else(|status| {
+            MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: status.code() as i32,
+                error_message: status.message().to_string(),
+            })
+        });
+
+        ServerReflectionResponse {
+            valid_host: request.host.clone(),
+            original_request: Some(request),
+            message_response: Some(response),
+        }
+    }
+}
+
+fn file_descriptor_response(file_descriptor_proto: Vec<Vec<u8>>) -> MessageResponse {
+    MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+        file_descriptor_proto,
+    })
+}
+
+pub struct ReflectionService {
+    index: Arc<Index>,
+}
+
+#[crate::async_trait]
+impl ServerReflection for ReflectionService {
+    type ServerReflectionInfoStream =
+        Pin<Box<dyn Stream<Item = Result<ServerReflectionResponse, Status>> + Send + 'static>>;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let index = self.index.clone();
+        let responses = request
+            .into_inner()
+            .map(move |request| request.map(|request| index.respond(request)));
+
+        Ok(Response::new(Box::pin(responses)))
+    }
+}
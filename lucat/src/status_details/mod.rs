@@ -0,0 +1,246 @@
+use bytes::Bytes;
+use prost::{DecodeError, Message};
+use prost_types::Any;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::{Code, Status};
+
+pub mod pb {
+    crate::include_proto!("google.rpc");
+}
+
+pub use pb::bad_request::FieldViolation;
+pub use pb::quota_failure::Violation as QuotaViolation;
+pub use pb::{
+    BadRequest, DebugInfo, ErrorInfo, LocalizedMessage, QuotaFailure, ResourceInfo, RetryInfo,
+};
+
+const TYPE_URL_PREFIX: &str = "type.googleapis.com/";
+
+// A message that can be carried in the `details` of a `google.rpc.Status`.
+pub trait Detail: Message + Default {
+    const TYPE_NAME: &'static str;
+
+    fn to_any(&self) -> Any {
+        Any {
+            type_url: format!("{}{}", TYPE_URL_PREFIX, Self::TYPE_NAME),
+            value: self.encode_to_vec(),
+        }
+    }
+
+    fn from_any(any: &Any) -> Result<Option<Self>, DecodeError> {
+        match any.type_url.strip_prefix(TYPE_URL_PREFIX) {
+            Some(name) if name == Self::TYPE_NAME => Self::decode(&any.value[..]).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+macro_rules! detail {
+    ($($ty:ident => $name:expr,)*) => {
+        $(impl Detail for $ty {
+            const TYPE_NAME: &'static str = $name;
+        })*
+    };
+}
+
+detail! {
+    ErrorInfo => "google.rpc.ErrorInfo",
+    RetryInfo => "google.rpc.RetryInfo",
+    DebugInfo => "google.rpc.DebugInfo",
+    QuotaFailure => "google.rpc.QuotaFailure",
+    BadRequest => "google.rpc.BadRequest",
+    ResourceInfo => "google.rpc.ResourceInfo",
+    LocalizedMessage => "google.rpc.LocalizedMessage",
+}
+
+pub fn builder(code: Code, message: impl Into<String>) -> Builder {
+    Builder {
+        code,
+        message: message.into(),
+        details: Vec::new(),
+        bad_request: BadRequest::default(),
+        quota_failure: QuotaFailure::default(),
+    }
+}
+
+pub struct Builder {
+    code: Code,
+    message: String,
+    details: Vec<Any>,
+    // violations are collected into a single detail each
+    bad_request: BadRequest,
+    quota_failure: QuotaFailure,
+}
+
+impl Builder {
+    pub fn error_info(
+        self,
+        reason: impl Into<String>,
+        domain: impl Into<String>,
+        metadata: HashMap<String, String>,
+    ) -> Self {
+        self.detail(&ErrorInfo {
+            reason: reason.into(),
+            domain: domain.into(),
+            metadata,
+        })
+    }
+
+    pub fn field_violation(
+        mut self,
+        field: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.bad_request.field_violations.push(FieldViolation {
+            field: field.into(),
+            description: description.into(),
+        });
+        self
+    }
+
+    pub fn quota_violation(
+        mut self,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.quota_failure.violations.push(QuotaViolation {
+            subject: subject.into(),
+            description: description.into(),
+        });
+        self
+    }
+
+    pub fn retry_info(self, retry_delay: Duration) -> Self {
+        self.detail(&RetryInfo {
+            retry_delay: Some(retry_delay.into()),
+        })
+    }
+
+    pub fn resource_info(
+        self,
+        resource_type: impl Into<String>,
+        resource_name: impl Into<String>,
+        owner: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.detail(&ResourceInfo {
+            resource_type: resource_type.into(),
+            resource_name: resource_name.into(),
+            owner: owner.into(),
+            description: description.into(),
+        })
+    }
+
+    pub fn debug_info(self, stack_entries: Vec<String>, detail: impl Into<String>) -> Self {
+        self.detail(&DebugInfo {
+            stack_entries,
+            detail: detail.into(),
+        })
+    }
+
+    pub fn localized_message(self, locale: impl Into<String>, message: impl Into<String>) -> Self {
+        self.detail(&LocalizedMessage {
+            locale: locale.into(),
+            message: message.into(),
+        })
+    }
+
+    pub fn detail<D: Detail>(self, detail: &D) -> Self {
+        self.any(detail.to_any())
+    }
+
+    // for details of types this module does not know about
+    pub fn any(mut self, any: Any) -> Self {
+        self.details.push(any);
+        self
+    }
+
+    pub fn build(mut self) -> Status {
+        if !self.bad_request.field_violations.is_empty() {
+            self.details.push(self.bad_request.to_any());
+        }
+        if !self.quota_failure.violations.is_empty() {
+            self.details.push(self.quota_failure.to_any());
+        }
+
+        let status = pb::Status {
+            code: self.code as i32,
+            message: self.message.clone(),
+            details: self.details,
+        };
+        Status::with_details(self.code, self.message, Bytes::from(status.encode_to_vec()))
+    }
+}
+
+// Decodes the `google.rpc.Status` carried by `status`. A status without
+// details gives empty `ErrorDetails`.
+pub fn extract(status: &Status) -> Result<ErrorDetails, DecodeError> {
+    let status = pb::Status::decode(status.details())?;
+    Ok(ErrorDetails {
+        details: status.details,
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorDetails {
+    details: Vec<Any>,
+}
+
+impl ErrorDetails {
+    // The first detail of type `D`, if any.
+    pub fn get<D: Detail>(&self) -> Result<Option<D>, DecodeError> {
+        for any in self.details.iter() {
+            if let Some(detail) = D::from_any(any)? {
+                return Ok(Some(detail));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn error_info(&self) -> Option<ErrorInfo> {
+        self.get().ok().flatten()
+    }
+
+    pub fn bad_request(&self) -> Option<BadRequest> {
+        self.get().ok().flatten()
+    }
+
+    pub fn field_violations(&self) -> Vec<FieldViolation> {
+        self.bad_request()
+            .map(|bad_request| bad_request.field_violations)
+            .unwrap_or_default()
+    }
+
+    pub fn quota_failure(&self) -> Option<QuotaFailure> {
+        self.get().ok().flatten()
+    }
+
+    pub fn retry_info(&self) -> Option<RetryInfo> {
+        self.get().ok().flatten()
+    }
+
+    pub fn retry_delay(&self) -> Option<Duration> {
+        let delay = self.retry_info()?.retry_delay?;
+        Duration::try_from(delay).ok()
+    }
+
+    pub fn resource_info(&self) -> Option<ResourceInfo> {
+        self.get().ok().flatten()
+    }
+
+    pub fn debug_info(&self) -> Option<DebugInfo> {
+        self.get().ok().flatten()
+    }
+
+    pub fn localized_message(&self) -> Option<LocalizedMessage> {
+        self.get().ok().flatten()
+    }
+
+    // every detail, including those of types this module does not know about
+    pub fn details(&self) -> &[Any] {
+        &self.details
+    }
+}
@@ -0,0 +1,78 @@
+use bytes::Bytes;
+use cynthia::runtime::future;
+use nephele::proto::h2::{self, Reason, SendStream};
+use std::io;
+use std::task::{Context, Poll};
+
+use crate::common::{Body, Status};
+
+pub(crate) enum Next {
+    Data(Bytes),
+    End,
+    Failed(Status),
+    Reset,
+}
+
+// Waits for the next chunk of a body that is being sent, giving up as soon
+// as the peer resets the stream.
+pub(crate) async fn next<F>(body: &mut Body, mut poll_reset: F) -> Next
+where
+    F: FnMut(&mut Context<'_>) -> Poll<Result<Reason, h2::Error>>,
+{
+    future::poll_fn(|cx| {
+        if poll_reset(cx).is_ready() {
+            return Poll::Ready(Next::Reset);
+        }
+
+        body.poll_data(cx).map(|data| match data {
+            Some(Ok(data)) => Next::Data(data),
+            Some(Err(status)) => Next::Failed(status),
+            None => Next::End,
+        })
+    })
+    .await
+}
+
+pub(crate) async fn send_data(
+    send: &mut SendStream<Bytes>,
+    mut data: Bytes,
+    end_of_stream: bool,
+) -> Result<(), crate::Error> {
+    if data.is_empty() {
+        if end_of_stream {
+            send.send_data(data, true)?;
+        }
+        return Ok(());
+    }
+
+    // only hand h2 as much as the peer's window allows, so a slow reader
+    // holds back the body instead of having it buffered in memory
+    while !data.is_empty() {
+        send.reserve_capacity(data.len());
+        match future::poll_fn(|cx| send.poll_capacity(cx)).await {
+            Some(Ok(0)) => {}
+            Some(Ok(capacity)) => {
+                let chunk = data.split_to(capacity.min(data.len()));
+                send.send_data(chunk, end_of_stream && data.is_empty())?;
+            }
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(io::Error::from(io::ErrorKind::BrokenPipe).into()),
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn send_body(send: &mut SendStream<Bytes>, mut body: Body) -> Result<(), crate::Error> {
+    loop {
+        match next(&mut body, |cx| send.poll_reset(cx)).await {
+            Next::Data(data) => send_data(send, data, false).await?,
+            Next::End => return send_data(send, Bytes::new(), true).await,
+            Next::Failed(status) => {
+                send.send_reset(Reason::CANCEL);
+                return Err(status.into());
+            }
+            Next::Reset => return Ok(()),
+        }
+    }
+}
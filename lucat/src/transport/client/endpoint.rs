@@ -1,37 +1,243 @@
 use anyhow::Result;
-use http::{HeaderMap, Request};
-use cynthia::runtime::{self, transport};
-use nephele::proto::h2::client;
+use bytes::Bytes;
+use http::Request;
+use cynthia::runtime::{self, future, transport, Async};
+use cynthia::runtime::channel::{self, Sender};
+use cynthia::runtime::stream::{Stream, StreamExt};
+use cynthia::runtime::swap::{AsyncRead, AsyncWrite};
+use nephele::proto::h2::client::SendRequest;
 use http::{
-    header::{HeaderValue, CONTENT_TYPE},
+    header::{HeaderValue, CONTENT_TYPE, TE},
+    uri::PathAndQuery,
 };
 use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::Poll;
+use std::time::Duration;
+use tracing::debug;
+use crate::codec::{ContentType, MessageSizes};
 use crate::common::{self};
-use crate::common::{Body, Response};
-//use nephele::proto::h2::client::{SendRequest, Connection};
-//use cynthia::runtime::TcpStream;
+use crate::common::request::SanitizeHeaders;
+use crate::common::status::infer_grpc_status;
+use crate::common::{Body, Code, Response, Status};
+use crate::transport::body;
+use crate::transport::duplex::{duplex, DuplexStream};
+use crate::transport::ping::{self, InFlight, Ponged, Ponger, Recorded};
+use crate::transport::settings;
 
+const DUPLEX_BUF_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Debug)]
+enum Target {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+    Memory(Sender<DuplexStream>),
+}
+
+impl Target {
+    fn parse(dst: &str) -> Target {
+        #[cfg(unix)]
+        if let Some(path) = dst.strip_prefix("unix:") {
+            return Target::Unix(PathBuf::from(path.trim_start_matches("//")));
+        }
+        Target::Tcp(dst.to_string())
+    }
+
+    fn uri(&self, path: &str) -> String {
+        match self {
+            Target::Tcp(addr) => format!("http://{}{}", addr, path),
+            #[cfg(unix)]
+            Target::Unix(_) => format!("http://localhost{}", path),
+            Target::Memory(_) => format!("http://localhost{}", path),
+        }
+    }
+}
+
+// Clones share the connection, so one endpoint can serve concurrent calls.
+#[derive(Clone)]
 pub struct Endpoint {
-    dst: String,
-    //client: SendRequest<Bytes>,
-    //connection: Connection<TcpStream>,
-    //connection: Arc<RefCell<Connection<TcpStream>>>,
+    target: Target,
+    keepalive: ping::Config,
+    http2: settings::Http2,
+    tcp: settings::Tcp,
+    message_sizes: MessageSizes,
+    client: Arc<Mutex<Option<SendRequest<Bytes>>>>,
+    in_flight: Arc<AtomicUsize>,
 }
 
 
 impl Endpoint {
+    pub fn new(dst: String) -> Self {
+        Endpoint {
+            target: Target::parse(&dst),
+            keepalive: ping::Config::default(),
+            http2: settings::Http2::default(),
+            tcp: settings::Tcp::default(),
+            message_sizes: MessageSizes::default(),
+            client: Arc::new(Mutex::new(None)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn in_memory() -> (Self, impl Stream<Item = io::Result<DuplexStream>>) {
+        let (tx, rx) = channel::unbounded();
+        let mut endpoint = Endpoint::new(String::new());
+        endpoint.target = Target::Memory(tx);
+
+        (endpoint, rx.map(Ok))
+    }
+
     pub async fn connect(dst: String) -> Result<Self, crate::Error> {
-        //let stream = transport::TcpStream::connect("127.0.0.1:7000").await?;
-        //let (client, h2) = client::handshake(stream).await?;
-
-        Ok(
-            Endpoint {
-                dst: dst.clone(),
-                //client: client,
-                //connection: h2,
-                //connection: Arc::new(RefCell::new(h2)),
+        let mut endpoint = Endpoint::new(dst);
+        endpoint.ready().await?;
+
+        Ok(endpoint)
+    }
+
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive.keepalive_interval = Some(interval);
+        self
+    }
+
+    pub fn keepalive_timeout(mut self, timeout: Duration) -> Self {
+        self.keepalive.keepalive_timeout = timeout;
+        self
+    }
+
+    pub fn keepalive_while_idle(mut self, enabled: bool) -> Self {
+        self.keepalive.keepalive_while_idle = enabled;
+        self
+    }
+
+    pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_stream_window_size = Some(size);
+        self
+    }
+
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_connection_window_size = Some(size);
+        self
+    }
+
+    pub fn adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2.adaptive_window = enabled;
+        self
+    }
+
+    pub fn max_frame_size(mut self, max: u32) -> Self {
+        self.http2.max_frame_size = Some(max);
+        self
+    }
+
+    pub fn max_header_list_size(mut self, max: u32) -> Self {
+        self.http2.max_header_list_size = Some(max);
+        self
+    }
+
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp.nodelay = enabled;
+        self
+    }
+
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp.keepalive = Some(idle);
+        self
+    }
+
+    pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+        self.message_sizes.max_decoding = Some(limit);
+        self
+    }
+
+    async fn ready(&mut self) -> Result<SendRequest<Bytes>, crate::Error> {
+        let cached = self.client.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        if let Some(mut client) = cached {
+            match future::poll_fn(|cx| client.poll_ready(cx)).await {
+                Ok(()) => return Ok(client),
+                Err(e) => debug!("connection to {:?} lost: {}", self.target, e),
+            }
+        }
+
+        let client = match &self.target {
+            Target::Tcp(addr) => {
+                let stream = transport::TcpStream::connect(addr.as_str()).await?;
+                let socket: Arc<Async<TcpStream>> = stream.clone().into();
+                if let Err(e) = self.tcp.apply(socket.get_ref()) {
+                    debug!("failed to set socket options: {}", e);
+                }
+                self.handshake(stream).await?
+            }
+            #[cfg(unix)]
+            Target::Unix(path) => {
+                let stream = Async::<UnixStream>::connect(path).await?;
+                self.handshake(stream).await?
+            }
+            Target::Memory(incoming) => {
+                let (client, server) = duplex(DUPLEX_BUF_SIZE);
+                incoming
+                    .send(server)
+                    .await
+                    .map_err(|_| io::Error::new(io::ErrorKind::ConnectionRefused, "in-memory server is gone"))?;
+                self.handshake(client).await?
+            }
+        };
+
+        *self.client.lock().unwrap_or_else(PoisonError::into_inner) = Some(client.clone());
+        Ok(client)
+    }
+
+    async fn handshake<T>(&self, io: T) -> Result<SendRequest<Bytes>, crate::Error>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (io, received) = Recorded::new(io);
+        let (client, mut h2) = self.http2.client().handshake(io).await?;
+        let mut keepalive = self.keepalive.clone();
+        keepalive.bdp_initial_window = self.http2.bdp_initial_window();
+        let mut ponger = Ponger::new(h2.ping_pong(), &keepalive, received);
+        let in_flight = self.in_flight.clone();
+
+        runtime::spawn(async move {
+            let result = future::poll_fn(|cx| {
+                if let Some(ponger) = ponger.as_mut() {
+                    let is_idle = in_flight.load(Ordering::Acquire) == 0;
+                    while let Poll::Ready(ponged) = ponger.poll(cx, is_idle) {
+                        match ponged {
+                            Ponged::SizeUpdate(size) => {
+                                h2.set_target_window_size(size);
+                                if let Err(e) = h2.set_initial_window_size(size) {
+                                    debug!("failed to update window size: {}", e);
+                                }
+                            }
+                            Ponged::KeepAliveTimedOut => {
+                                debug!("closing connection, keepalive timed out");
+                                return Poll::Ready(Ok(()));
+                            }
+                        }
+                    }
+                }
+
+                Pin::new(&mut h2).poll(cx)
+            })
+            .await;
+
+            if let Err(e) = result {
+                debug!("connection error: {}", e);
             }
-        )
+        })
+        .detach();
+
+        Ok(client)
     }
 }
 
@@ -45,68 +251,68 @@ impl crate::SimpleInstantService for Endpoint {
 
 impl Endpoint {
     pub async fn request(&mut self, request: common::Request<Body>) -> Result<Response<Body>, Box<dyn Error + Send + Sync>> {
-        let dst = self.dst.clone();
-        let stream = transport::TcpStream::connect(dst).await?;
-        let (mut h2client, h2) = client::handshake(stream).await?;
-        
-        let mut http_request = Request::builder()
-            .method("POST")
-            .uri("http://127.0.0.1:7000")
-            .body(())
-            .unwrap();
-
-        *http_request.version_mut() = http::Version::HTTP_2;
-
-        http_request.headers_mut()
-            .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
-
-        let mut trailers = HeaderMap::new();
-        trailers.insert("zomg", "hello".parse().unwrap());
-
-        let (response, mut stream) = h2client.send_request(http_request, false)?;
-        let input_bytes = request.into_inner().data();
-
-        let input_bytes = match input_bytes {
-            Some(b) => b,
-            None => {
-                return Ok(common::Response::new(Body::new(None)));
-            }
-        };
+        let mut h2client = self.ready().await?;
+        let in_flight = InFlight::new(&self.in_flight);
 
+        let uri: http::Uri = match request.extensions().get::<PathAndQuery>() {
+            Some(path) => self.target.uri(path.as_str()),
+            None => self.target.uri("/"),
+        }
+        .parse()?;
+        let content_type = request
+            .extensions()
+            .get::<ContentType>()
+            .cloned()
+            .unwrap_or_default();
+
+        let (mut http_request, input) = request.into_http(uri, SanitizeHeaders::Yes).into_parts();
+        http_request.headers.insert(CONTENT_TYPE, content_type.0);
+        http_request.headers.insert(TE, HeaderValue::from_static("trailers"));
 
-        stream.send_data(input_bytes, false)?;
-        stream.send_trailers(trailers).unwrap();
+        let (response, mut stream) = h2client.send_request(Request::from_parts(http_request, ()), false)?;
 
+        // the request body is sent alongside the response so that both
+        // directions of a streaming call can make progress
         runtime::spawn(async move {
-            if let Err(e) = h2.await {
-                println!("GOT ERR={:?}", e);
+            if let Err(e) = body::send_body(&mut stream, input).await {
+                debug!("failed to send request body: {}", e);
             }
         })
         .detach();
-    
-        let response = response.await?;
-        let mut body = response.into_body();
 
-        while let Some(chunk) = body.data().await {
-    
-            let data = chunk?;
-            let datalen = data.len();
-            
-            let _ = body.flow_control().release_capacity(datalen);
+        let response = response.await?;
 
-            if let Some(_trailers) = body.trailers().await? {
+        // trailers-only responses and errors from intermediaries that don't
+        // speak gRPC, like a proxy answering 502, end the call right here
+        match infer_grpc_status(Some(response.headers()), response.status()) {
+            Ok(()) => {
+                // the headers of a trailers-only response are its trailers too
+                let trailers = Status::from_header_map(response.headers())
+                    .map(|status| status.metadata().clone())
+                    .unwrap_or_default();
+                let body = Body::empty().with_trailers(trailers);
+                return Ok(common::Response::from_http(response.map(|_| body)));
             }
-
-            let r = common::Response::new(Body::new(Some(data)));
-
-            return Ok(r);
+            Err(Some(status)) => return Ok(common::Response::new(Body::error(status))),
+            Err(None) => {}
         }
-    
-        if let Some(_trailers) = body.trailers().await? {
+
+        let content_type = response.headers().get(CONTENT_TYPE).map(HeaderValue::as_bytes);
+        if !content_type.is_some_and(ContentType::is_grpc) {
+            let status = Status::new(
+                Code::Unknown,
+                format!(
+                    "invalid content-type {:?} in response",
+                    String::from_utf8_lossy(content_type.unwrap_or_default())
+                ),
+            );
+            return Ok(common::Response::new(Body::error(status)));
         }
-    
-        let r = common::Response::new(Body::new(None));
 
-        Ok(r)
+        let mut body = response.map(|recv| Body::h2(recv).status_required().in_flight(in_flight));
+        body.extensions_mut().insert(self.message_sizes);
+
+        // the response headers are the first part of the call's metadata
+        Ok(common::Response::from_http(body))
     }
 }
@@ -2,6 +2,13 @@ pub mod client;
 pub mod server;
 pub mod error;
 
+mod body;
+mod duplex;
+pub(crate) mod ping;
+mod settings;
+
 pub use client::Endpoint;
+pub use duplex::{duplex, DuplexStream};
+pub use server::NamedService;
 
 pub use self::error::Error;
\ No newline at end of file
@@ -0,0 +1,295 @@
+use cynthia::io::Timer;
+use cynthia::runtime::swap::{AsyncRead, AsyncWrite};
+use nephele::proto::h2::{Ping, PingPong};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+pub(crate) const DEFAULT_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+// Spec default for both the stream and the connection window.
+pub(crate) const DEFAULT_WINDOW_SIZE: u32 = 65_535;
+
+const BDP_LIMIT: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) keepalive_timeout: Duration,
+    pub(crate) keepalive_while_idle: bool,
+    pub(crate) bdp_initial_window: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keepalive_interval: None,
+            keepalive_timeout: DEFAULT_KEEPALIVE_TIMEOUT,
+            keepalive_while_idle: false,
+            bdp_initial_window: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Ponged {
+    SizeUpdate(u32),
+    KeepAliveTimedOut,
+}
+
+// Counts the bytes read off a connection so the BDP estimator knows how much
+// data arrived during a ping round trip.
+pub(crate) struct Recorded<T> {
+    io: T,
+    received: Arc<AtomicUsize>,
+}
+
+impl<T> Recorded<T> {
+    pub(crate) fn new(io: T) -> (Self, Arc<AtomicUsize>) {
+        let received = Arc::new(AtomicUsize::new(0));
+        let recorded = Recorded {
+            io,
+            received: received.clone(),
+        };
+
+        (recorded, received)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Recorded<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.io).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            this.received.fetch_add(n, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Recorded<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
+
+// Tracks the streams open on a connection, which decides whether keepalive
+// pings are sent while idle.
+pub(crate) struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    pub(crate) fn new(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::AcqRel);
+        InFlight(count.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+enum State {
+    Scheduled,
+    PingSent,
+}
+
+struct KeepAlive {
+    interval: Duration,
+    timeout: Duration,
+    while_idle: bool,
+    state: State,
+    timer: Timer,
+}
+
+struct Bdp {
+    window: u32,
+    bytes_at_ping: usize,
+}
+
+impl Bdp {
+    fn sample(&mut self, bytes: usize) -> Option<u32> {
+        // Only grow once the round trip carried at least 2/3 of the window.
+        if bytes < self.window as usize * 2 / 3 {
+            return None;
+        }
+
+        let window = (bytes * 2).min(BDP_LIMIT) as u32;
+        if window <= self.window {
+            return None;
+        }
+
+        trace!("bdp window {} -> {}", self.window, window);
+        self.window = window;
+        Some(window)
+    }
+}
+
+pub(crate) struct Ponger {
+    ping_pong: PingPong,
+    received: Arc<AtomicUsize>,
+    ping_sent_at: Option<Instant>,
+    keepalive: Option<KeepAlive>,
+    bdp: Option<Bdp>,
+}
+
+impl Ponger {
+    pub(crate) fn new(
+        ping_pong: Option<PingPong>,
+        config: &Config,
+        received: Arc<AtomicUsize>,
+    ) -> Option<Ponger> {
+        let keepalive = config.keepalive_interval.map(|interval| KeepAlive {
+            interval,
+            timeout: config.keepalive_timeout,
+            while_idle: config.keepalive_while_idle,
+            state: State::Scheduled,
+            timer: Timer::after(interval),
+        });
+        let bdp = config.bdp_initial_window.map(|window| Bdp {
+            window,
+            bytes_at_ping: 0,
+        });
+
+        if keepalive.is_none() && bdp.is_none() {
+            return None;
+        }
+
+        Some(Ponger {
+            ping_pong: ping_pong?,
+            received,
+            ping_sent_at: None,
+            keepalive,
+            bdp,
+        })
+    }
+
+    fn send_ping(&mut self) -> bool {
+        match self.ping_pong.send_ping(Ping::opaque()) {
+            Ok(()) => {
+                trace!("ping sent");
+                self.ping_sent_at = Some(Instant::now());
+                true
+            }
+            Err(e) => {
+                debug!("ping error: {}", e);
+                false
+            }
+        }
+    }
+
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>, is_idle: bool) -> Poll<Ponged> {
+        loop {
+            if let Some(sent_at) = self.ping_sent_at {
+                match self.ping_pong.poll_pong(cx) {
+                    Poll::Ready(Ok(_)) => {
+                        trace!("pong received after {:?}", sent_at.elapsed());
+                        self.ping_sent_at = None;
+
+                        if let Some(keepalive) = self.keepalive.as_mut() {
+                            keepalive.state = State::Scheduled;
+                            keepalive.timer.set_after(keepalive.interval);
+                        }
+
+                        let received = self.received.load(Ordering::Relaxed);
+                        if let Some(bdp) = self.bdp.as_mut() {
+                            let bytes = received - bdp.bytes_at_ping;
+                            bdp.bytes_at_ping = received;
+                            if let Some(window) = bdp.sample(bytes) {
+                                return Poll::Ready(Ponged::SizeUpdate(window));
+                            }
+                        }
+                        continue;
+                    }
+                    Poll::Ready(Err(e)) => {
+                        debug!("pong error: {}", e);
+                        self.ping_sent_at = None;
+                        self.bdp = None;
+                        if self.keepalive.is_some() {
+                            return Poll::Ready(Ponged::KeepAliveTimedOut);
+                        }
+                        return Poll::Pending;
+                    }
+                    Poll::Pending => {}
+                }
+            }
+
+            if let Some(mut keepalive) = self.keepalive.take() {
+                let fired = Pin::new(&mut keepalive.timer).poll(cx).is_ready();
+                if fired {
+                    match keepalive.state {
+                        State::Scheduled if is_idle && !keepalive.while_idle => {
+                            keepalive.timer.set_after(keepalive.interval);
+                        }
+                        State::Scheduled => {
+                            // a BDP ping already in flight doubles as keepalive
+                            if self.ping_sent_at.is_some() || self.send_ping() {
+                                keepalive.state = State::PingSent;
+                                keepalive.timer.set_after(keepalive.timeout);
+                            } else {
+                                keepalive.timer.set_after(keepalive.interval);
+                            }
+                        }
+                        State::PingSent => {
+                            debug!("keepalive timed out after {:?}", keepalive.timeout);
+                            self.keepalive = Some(keepalive);
+                            return Poll::Ready(Ponged::KeepAliveTimedOut);
+                        }
+                    }
+                }
+
+                self.keepalive = Some(keepalive);
+                if fired {
+                    continue;
+                }
+            }
+
+            if self.ping_sent_at.is_none() {
+                let received = self.received.load(Ordering::Relaxed);
+                let sample = match self.bdp.as_ref() {
+                    Some(bdp) => received > bdp.bytes_at_ping && (bdp.window as usize) < BDP_LIMIT,
+                    None => false,
+                };
+
+                if sample && self.send_ping() {
+                    if let Some(bdp) = self.bdp.as_mut() {
+                        bdp.bytes_at_ping = received;
+                    }
+                    continue;
+                }
+            }
+
+            return Poll::Pending;
+        }
+    }
+}
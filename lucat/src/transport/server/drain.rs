@@ -0,0 +1,122 @@
+use cynthia::future::timeout;
+use cynthia::platform::event::{Event, EventListener};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tracing::debug;
+
+const RUNNING: u8 = 0;
+const DRAINING: u8 = 1;
+const CLOSING: u8 = 2;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Signal {
+    Drain,
+    Close,
+}
+
+struct Shared {
+    state: AtomicU8,
+    connections: AtomicUsize,
+    event: Event,
+}
+
+pub(crate) struct Drain {
+    shared: Arc<Shared>,
+}
+
+impl Drain {
+    pub(crate) fn new() -> Self {
+        Drain {
+            shared: Arc::new(Shared {
+                state: AtomicU8::new(RUNNING),
+                connections: AtomicUsize::new(0),
+                event: Event::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn watcher(&self) -> Watcher {
+        self.shared.connections.fetch_add(1, Ordering::AcqRel);
+        Watcher {
+            shared: self.shared.clone(),
+            listener: None,
+            seen: RUNNING,
+        }
+    }
+
+    pub(crate) async fn drain(self, deadline: Duration) {
+        debug!(
+            "draining {} connections",
+            self.shared.connections.load(Ordering::Acquire)
+        );
+        self.signal(DRAINING);
+
+        if timeout(deadline, self.wait_closed()).await.is_err() {
+            debug!("drain deadline elapsed, closing remaining connections");
+            self.signal(CLOSING);
+            self.wait_closed().await;
+        }
+    }
+
+    fn signal(&self, state: u8) {
+        self.shared.state.store(state, Ordering::Release);
+        self.shared.event.notify(usize::MAX);
+    }
+
+    async fn wait_closed(&self) {
+        loop {
+            if self.shared.connections.load(Ordering::Acquire) == 0 {
+                return;
+            }
+
+            let listener = self.shared.event.listen();
+            if self.shared.connections.load(Ordering::Acquire) == 0 {
+                return;
+            }
+
+            listener.await;
+        }
+    }
+}
+
+pub(crate) struct Watcher {
+    shared: Arc<Shared>,
+    listener: Option<EventListener>,
+    seen: u8,
+}
+
+impl Watcher {
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Signal> {
+        loop {
+            let state = self.shared.state.load(Ordering::Acquire);
+            if state > self.seen {
+                self.seen = state;
+                return match state {
+                    DRAINING => Poll::Ready(Signal::Drain),
+                    _ => Poll::Ready(Signal::Close),
+                };
+            }
+
+            match self.listener.as_mut() {
+                Some(listener) => {
+                    if Pin::new(listener).poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    self.listener = None;
+                }
+                None => self.listener = Some(self.shared.event.listen()),
+            }
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.shared.connections.fetch_sub(1, Ordering::AcqRel);
+        self.shared.event.notify(usize::MAX);
+    }
+}
@@ -0,0 +1,75 @@
+use cynthia::io::Timer;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Config {
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) max_age_grace: Option<Duration>,
+    pub(crate) max_idle: Option<Duration>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Expired {
+    Age,
+    Grace,
+    Idle,
+}
+
+pub(crate) struct Lifetime {
+    age: Option<Timer>,
+    grace: Option<Duration>,
+    grace_timer: Option<Timer>,
+    idle: Option<Duration>,
+    idle_timer: Option<Timer>,
+}
+
+impl Lifetime {
+    pub(crate) fn new(config: &Config) -> Self {
+        Lifetime {
+            age: config.max_age.map(Timer::after),
+            grace: config.max_age_grace,
+            grace_timer: None,
+            idle: config.max_idle,
+            idle_timer: None,
+        }
+    }
+
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>, is_idle: bool) -> Poll<Expired> {
+        if let Some(timer) = self.age.as_mut() {
+            if Pin::new(timer).poll(cx).is_ready() {
+                self.age = None;
+                // without a grace period the connection lives until its
+                // streams finish on their own
+                if let Some(grace) = self.grace {
+                    self.grace_timer = Some(Timer::after(grace));
+                }
+                return Poll::Ready(Expired::Age);
+            }
+        }
+
+        if let Some(timer) = self.grace_timer.as_mut() {
+            if Pin::new(timer).poll(cx).is_ready() {
+                self.grace_timer = None;
+                return Poll::Ready(Expired::Grace);
+            }
+        }
+
+        if let Some(idle) = self.idle {
+            if is_idle {
+                let timer = self.idle_timer.get_or_insert_with(|| Timer::after(idle));
+                if Pin::new(timer).poll(cx).is_ready() {
+                    self.idle = None;
+                    self.idle_timer = None;
+                    return Poll::Ready(Expired::Idle);
+                }
+            } else {
+                self.idle_timer = None;
+            }
+        }
+
+        Poll::Pending
+    }
+}
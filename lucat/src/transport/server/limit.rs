@@ -0,0 +1,170 @@
+use cynthia::future::timeout;
+use cynthia::platform::lock::{Semaphore, SemaphoreGuardArc};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::common::Status;
+
+const SHED_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limits {
+    pub(crate) global: Option<Arc<Semaphore>>,
+    pub(crate) per_connection: Option<usize>,
+    pub(crate) queue_timeout: Option<Duration>,
+    pub(crate) load_shed: Option<Arc<LoadShed>>,
+}
+
+impl Limits {
+    pub(crate) fn connection(&self) -> ConnectionLimit {
+        ConnectionLimit {
+            global: self.global.clone(),
+            local: self.per_connection.map(|n| Arc::new(Semaphore::new(n))),
+            queue_timeout: self.queue_timeout,
+            load_shed: self.load_shed.clone(),
+        }
+    }
+}
+
+pub(crate) struct Permit {
+    _local: Option<SemaphoreGuardArc>,
+    _global: Option<SemaphoreGuardArc>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectionLimit {
+    global: Option<Arc<Semaphore>>,
+    local: Option<Arc<Semaphore>>,
+    queue_timeout: Option<Duration>,
+    load_shed: Option<Arc<LoadShed>>,
+}
+
+impl ConnectionLimit {
+    pub(crate) async fn acquire(&self) -> Result<Permit, Status> {
+        if let Some(permit) = self.try_acquire() {
+            self.record(Duration::from_secs(0));
+            return Ok(permit);
+        }
+
+        if let Some(load_shed) = self.load_shed.as_ref() {
+            if load_shed.is_overloaded() {
+                debug!("shedding request, queue latency above target");
+                return Err(Status::unavailable("server is overloaded"));
+            }
+        }
+
+        let queue_timeout = match self.queue_timeout {
+            Some(queue_timeout) => queue_timeout,
+            None => return Err(Status::resource_exhausted("too many in-flight requests")),
+        };
+
+        let queued = Instant::now();
+        match timeout(queue_timeout, self.acquire_wait()).await {
+            Ok(permit) => {
+                self.record(queued.elapsed());
+                Ok(permit)
+            }
+            Err(_) => {
+                self.record(queued.elapsed());
+                Err(Status::unavailable("timed out waiting for an in-flight slot"))
+            }
+        }
+    }
+
+    fn try_acquire(&self) -> Option<Permit> {
+        let local = match self.local.as_ref() {
+            Some(local) => Some(local.try_acquire_arc()?),
+            None => None,
+        };
+        let global = match self.global.as_ref() {
+            Some(global) => Some(global.try_acquire_arc()?),
+            None => None,
+        };
+
+        Some(Permit {
+            _local: local,
+            _global: global,
+        })
+    }
+
+    async fn acquire_wait(&self) -> Permit {
+        let local = match self.local.as_ref() {
+            Some(local) => Some(local.acquire_arc().await),
+            None => None,
+        };
+        let global = match self.global.as_ref() {
+            Some(global) => Some(global.acquire_arc().await),
+            None => None,
+        };
+
+        Permit {
+            _local: local,
+            _global: global,
+        }
+    }
+
+    fn record(&self, delay: Duration) {
+        if let Some(load_shed) = self.load_shed.as_ref() {
+            load_shed.record(delay);
+        }
+    }
+}
+
+// Sheds load once the smallest queueing delay seen over an interval stays above
+// the target, i.e. the queue never drains (CoDel style).
+#[derive(Debug)]
+pub(crate) struct LoadShed {
+    target: Duration,
+    state: Mutex<ShedState>,
+}
+
+#[derive(Debug)]
+struct ShedState {
+    interval_start: Instant,
+    min_delay: Option<Duration>,
+    overloaded: bool,
+}
+
+impl LoadShed {
+    pub(crate) fn new(target: Duration) -> Self {
+        LoadShed {
+            target,
+            state: Mutex::new(ShedState {
+                interval_start: Instant::now(),
+                min_delay: None,
+                overloaded: false,
+            }),
+        }
+    }
+
+    fn record(&self, delay: Duration) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        state.min_delay = Some(state.min_delay.map_or(delay, |min| min.min(delay)));
+        self.roll(&mut state);
+    }
+
+    fn is_overloaded(&self) -> bool {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        self.roll(&mut state);
+        state.overloaded
+    }
+
+    fn roll(&self, state: &mut ShedState) {
+        if state.interval_start.elapsed() < SHED_INTERVAL {
+            return;
+        }
+
+        state.overloaded = matches!(state.min_delay, Some(min) if min > self.target);
+        state.min_delay = None;
+        state.interval_start = Instant::now();
+    }
+}
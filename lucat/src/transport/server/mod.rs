@@ -1,2 +1,9 @@
 pub mod server;
+mod drain;
+mod lifetime;
+mod limit;
+mod policy;
+mod recover;
+mod service;
 pub use server::Server;
+pub use service::{Fallback, FallbackService, NamedService, Or, Unimplemented};
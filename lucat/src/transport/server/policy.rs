@@ -0,0 +1,196 @@
+use cynthia::future::swap::{AsyncRead, AsyncWrite};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const PREFACE_LEN: usize = 24;
+const FRAME_HEADER_LEN: usize = 9;
+
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_PING: u8 = 0x6;
+const FLAG_ACK: u8 = 0x1;
+
+const MAX_PING_STRIKES: u32 = 2;
+
+// h2 answers PING frames on its own and never tells the server about them, so
+// the frames are sniffed off the wire here. Peers that keep pinging faster than
+// `min_interval` get their connection closed with ENHANCE_YOUR_CALM. Like gRPC,
+// strikes are forgiven whenever the server sends headers or data, so a peer
+// can't wipe its own slate by interleaving frames of its own.
+pub(crate) struct PingPolicy<T> {
+    io: T,
+    received: FrameSniffer,
+    sent: FrameSniffer,
+    strikes: Strikes,
+}
+
+impl<T> PingPolicy<T> {
+    pub(crate) fn new(io: T, min_interval: Duration) -> (Self, Arc<AtomicBool>) {
+        let violated = Arc::new(AtomicBool::new(false));
+        let policy = PingPolicy {
+            io,
+            received: FrameSniffer::new(PREFACE_LEN),
+            sent: FrameSniffer::new(0),
+            strikes: Strikes {
+                min_interval,
+                last_ping: None,
+                strikes: 0,
+                violated: violated.clone(),
+            },
+        };
+
+        (policy, violated)
+    }
+}
+
+struct Strikes {
+    min_interval: Duration,
+    last_ping: Option<Instant>,
+    strikes: u32,
+    violated: Arc<AtomicBool>,
+}
+
+impl Strikes {
+    fn on_sent(&mut self, kind: u8) {
+        if let FRAME_HEADERS | FRAME_DATA = kind {
+            self.strikes = 0;
+        }
+    }
+
+    fn on_received(&mut self, kind: u8, flags: u8) {
+        match kind {
+            FRAME_PING if flags & FLAG_ACK == 0 => {
+                let now = Instant::now();
+                if let Some(last) = self.last_ping {
+                    if now.duration_since(last) < self.min_interval {
+                        self.strikes += 1;
+                        if self.strikes > MAX_PING_STRIKES {
+                            debug!("too many pings from client, strikes: {}", self.strikes);
+                            self.violated.store(true, Ordering::Release);
+                        }
+                    }
+                }
+                self.last_ping = Some(now);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for PingPolicy<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let n = match Pin::new(&mut this.io).poll_read(cx, buf) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+
+        let strikes = &mut this.strikes;
+        this.received
+            .feed(&buf[..n], |kind, flags| strikes.on_received(kind, flags));
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for PingPolicy<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let n = match Pin::new(&mut this.io).poll_write(cx, buf) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+
+        let strikes = &mut this.strikes;
+        this.sent.feed(&buf[..n], |kind, _| strikes.on_sent(kind));
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let n = match Pin::new(&mut this.io).poll_write_vectored(cx, bufs) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+
+        let strikes = &mut this.strikes;
+        let mut left = n;
+        for buf in bufs {
+            let written = left.min(buf.len());
+            this.sent.feed(&buf[..written], |kind, _| strikes.on_sent(kind));
+            left -= written;
+        }
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
+
+struct FrameSniffer {
+    preface: usize,
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    payload: usize,
+}
+
+impl FrameSniffer {
+    fn new(preface: usize) -> Self {
+        FrameSniffer {
+            preface,
+            header: [0; FRAME_HEADER_LEN],
+            header_len: 0,
+            payload: 0,
+        }
+    }
+
+    fn feed(&mut self, mut buf: &[u8], mut on_frame: impl FnMut(u8, u8)) {
+        while !buf.is_empty() {
+            if self.preface > 0 {
+                let n = self.preface.min(buf.len());
+                self.preface -= n;
+                buf = &buf[n..];
+            } else if self.payload > 0 {
+                let n = self.payload.min(buf.len());
+                self.payload -= n;
+                buf = &buf[n..];
+            } else {
+                let n = (FRAME_HEADER_LEN - self.header_len).min(buf.len());
+                self.header[self.header_len..self.header_len + n].copy_from_slice(&buf[..n]);
+                self.header_len += n;
+                buf = &buf[n..];
+
+                if self.header_len == FRAME_HEADER_LEN {
+                    let h = &self.header;
+                    self.payload = (h[0] as usize) << 16 | (h[1] as usize) << 8 | h[2] as usize;
+                    self.header_len = 0;
+                    on_frame(h[3], h[4]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use cynthia::runtime::future::FutureExt;
+use cynthia::runtime::stream;
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::task::Poll;
+use tracing::warn;
+
+use crate::common::{Body, Response, Status};
+
+// Runs a service call so that a panic, while handling the call or while
+// producing its response, fails that call with `Internal` instead of taking
+// the connection down with it.
+pub(crate) async fn call<F, E>(call: F) -> Result<Response<Body>, E>
+where
+    F: Future<Output = Result<Response<Body>, E>>,
+{
+    match AssertUnwindSafe(call).catch_unwind().await {
+        Ok(Ok(response)) => Ok(response.map(guard)),
+        Ok(Err(e)) => Err(e),
+        Err(panic) => Ok(Response::new(Body::error(panicked(panic)))),
+    }
+}
+
+fn guard(mut body: Body) -> Body {
+    let mut done = false;
+    Body::stream(stream::poll_fn(move |cx| {
+        if done {
+            return Poll::Ready(None);
+        }
+        match panic::catch_unwind(AssertUnwindSafe(|| body.poll_data(cx))) {
+            Ok(polled) => polled,
+            Err(panic) => {
+                done = true;
+                Poll::Ready(Some(Err(panicked(panic))))
+            }
+        }
+    }))
+}
+
+// The panic message stays in the server log, the client only learns that the
+// call failed.
+fn panicked(panic: Box<dyn Any + Send>) -> Status {
+    let message = match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic.downcast_ref::<String>().map(String::as_str).unwrap_or("unknown panic"),
+    };
+    warn!("service panicked: {}", message);
+    Status::internal("service panicked")
+}
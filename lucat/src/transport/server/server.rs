@@ -1,28 +1,180 @@
-use std::net::{TcpListener, TcpStream};
-use http::{HeaderMap};
-use cynthia::runtime::{self, Async};
-use nephele::proto::h2::server;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+use http::{HeaderMap, Method, StatusCode};
+use http::header::{HeaderValue, CONTENT_TYPE, TE};
+use cynthia::platform::lock::Semaphore;
+use cynthia::runtime::{self, future, Async};
+use cynthia::runtime::stream::{Stream, StreamExt};
+use cynthia::runtime::swap::{AsyncRead, AsyncWrite};
+use nephele::proto::h2::{server::{self, SendResponse}, Reason, RecvStream};
+use bytes::Bytes;
 use std::error::Error;
+use tracing::debug;
+use crate::codec::{ContentType, MessageSizes};
 use crate::runtime::Service;
-use crate::common::{self, Body, Request, Response};
+use crate::common::{self, Body, Request, Response, Status};
+use crate::transport::ping::{self, InFlight, Ponged, Ponger, Recorded};
+use crate::transport::body::{self, Next};
+use crate::transport::settings;
+use super::drain::{Drain, Signal, Watcher};
+use super::lifetime::{self, Expired, Lifetime};
+use super::limit::{LoadShed, Limits};
+use super::policy::PingPolicy;
+use super::recover;
+use super::service::{Fallback, FallbackService, NamedService, Or, Unimplemented};
+
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+// gRPC's default, pings any closer than this earn the client a strike
+const DEFAULT_MIN_PING_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Clone)]
 pub struct Server {
-    id: i32,
+    keepalive: ping::Config,
+    min_ping_interval: Duration,
+    drain_timeout: Duration,
+    http2: settings::Http2,
+    tcp: settings::Tcp,
+    limits: Limits,
+    lifetime: lifetime::Config,
+    message_sizes: MessageSizes,
 }
 
 impl Server {
     pub fn builder() -> Self {
         Server {
-            id: 456,
+            keepalive: ping::Config::default(),
+            min_ping_interval: DEFAULT_MIN_PING_INTERVAL,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            http2: settings::Http2::default(),
+            tcp: settings::Tcp::default(),
+            limits: Limits::default(),
+            lifetime: lifetime::Config::default(),
+            message_sizes: MessageSizes::default(),
         }
     }
+
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive.keepalive_interval = Some(interval);
+        self
+    }
+
+    pub fn keepalive_timeout(mut self, timeout: Duration) -> Self {
+        self.keepalive.keepalive_timeout = timeout;
+        self
+    }
+
+    pub fn keepalive_while_idle(mut self, enabled: bool) -> Self {
+        self.keepalive.keepalive_while_idle = enabled;
+        self
+    }
+
+    pub fn min_ping_interval(mut self, interval: Duration) -> Self {
+        self.min_ping_interval = interval;
+        self
+    }
+
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_stream_window_size = Some(size);
+        self
+    }
+
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http2.initial_connection_window_size = Some(size);
+        self
+    }
+
+    pub fn adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2.adaptive_window = enabled;
+        self
+    }
+
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.http2.max_concurrent_streams = Some(max);
+        self
+    }
+
+    pub fn max_frame_size(mut self, max: u32) -> Self {
+        self.http2.max_frame_size = Some(max);
+        self
+    }
+
+    pub fn max_header_list_size(mut self, max: u32) -> Self {
+        self.http2.max_header_list_size = Some(max);
+        self
+    }
+
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp.nodelay = enabled;
+        self
+    }
+
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp.keepalive = Some(idle);
+        self
+    }
+
+    pub fn concurrency_limit(mut self, limit: usize) -> Self {
+        self.limits.global = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    pub fn concurrency_limit_per_connection(mut self, limit: usize) -> Self {
+        self.limits.per_connection = Some(limit);
+        self
+    }
+
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.queue_timeout = Some(timeout);
+        self
+    }
+
+    pub fn load_shed(mut self, target_latency: Duration) -> Self {
+        self.limits.load_shed = Some(Arc::new(LoadShed::new(target_latency)));
+        self
+    }
+
+    pub fn max_connection_age(mut self, age: Duration) -> Self {
+        self.lifetime.max_age = Some(age);
+        self
+    }
+
+    pub fn max_connection_age_grace(mut self, grace: Duration) -> Self {
+        self.lifetime.max_age_grace = Some(grace);
+        self
+    }
+
+    pub fn max_connection_idle(mut self, idle: Duration) -> Self {
+        self.lifetime.max_idle = Some(idle);
+        self
+    }
+
+    pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+        self.message_sizes.max_decoding = Some(limit);
+        self
+    }
+
+    pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+        self.message_sizes.max_encoding = Some(limit);
+        self
+    }
 }
 
 impl Server {
     pub fn register<S>(&mut self, svc: S) -> Router<S> 
     where 
-        S: Service<Request<Body>, Response = Response<Body>>
+        S: NamedService
+            + Service<Request<Body>, Response = Response<Body>>
             + Clone 
             + Send 
             + Sync 
@@ -33,6 +185,17 @@ impl Server {
         Router::new(self.clone(), svc)
     }
 
+    pub fn fallback<F>(&mut self, fallback: F) -> Router<Fallback<Unimplemented, F>>
+    where
+        F: FallbackService + Clone + Send + 'static,
+    {
+        Router {
+            server: self.clone(),
+            routes: Routes::new(Fallback::new(Vec::new(), Unimplemented, fallback)),
+            names: Vec::new(),
+        }
+    }
+
     pub async fn serve<S>(self, listener: Async<TcpListener>, route: Routes<S>) -> Result<(), Box<dyn Error + Send + Sync>>
     where
         S: Service<Request<Body>, Response = Response<Body>>
@@ -43,23 +206,112 @@ impl Server {
         S::Future: Send + 'static,
         <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
     {
+        self.serve_with_shutdown(listener, route, future::pending()).await
+    }
+
+    pub async fn serve_with_shutdown<S, F>(self, listener: Async<TcpListener>, route: Routes<S>, signal: F) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        F: Future<Output = ()>,
+    {
+        let tcp = self.tcp.clone();
+        let incoming = listener.incoming().map(move |stream| {
+            let stream = stream?;
+            if let Err(e) = tcp.apply(stream.get_ref()) {
+                debug!("failed to set socket options: {}", e);
+            }
+            Ok::<_, io::Error>(stream)
+        });
+
+        self.serve_with_incoming_shutdown(incoming, route, signal).await
+    }
+
+    pub async fn serve_with_incoming<S, I, IO, IE>(self, incoming: I, route: Routes<S>) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        IE: Into<common::Error>,
+    {
+        self.serve_with_incoming_shutdown(incoming, route, future::pending()).await
+    }
+
+    pub async fn serve_with_incoming_shutdown<S, I, IO, IE, F>(self, incoming: I, route: Routes<S>, signal: F) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        IE: Into<common::Error>,
+        F: Future<Output = ()>,
+    {
+        let drain = Drain::new();
+        let mut signal = Box::pin(signal);
+        let mut incoming = Box::pin(incoming);
+
         loop {
-            let (stream, _peer_addr) = listener.accept().await?;
-            let r = route.clone();
-            runtime::spawn(r.handle(stream)).detach();
+            let accept = async { incoming.next().await.map(Some) };
+            let shutdown = async {
+                signal.as_mut().await;
+                Some(None)
+            };
+
+            match future::or(shutdown, accept).await {
+                Some(Some(accepted)) => {
+                    // a failed accept, like running out of file descriptors,
+                    // only costs that connection
+                    let stream = match accepted {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            debug!("failed to accept connection: {}", e.into());
+                            continue;
+                        }
+                    };
+                    let r = route.clone();
+                    runtime::spawn(r.handle(stream, self.clone(), drain.watcher())).detach();
+                }
+                Some(None) => {
+                    debug!("shutdown signal received, no longer accepting connections");
+                    break;
+                }
+                None => break,
+            }
         }
+
+        drain.drain(self.drain_timeout).await;
+
+        Ok(())
     }
 }
 
 pub struct Router<S> {
     server: Server,
     routes: Routes<S>,
+    names: Vec<&'static str>,
 }
 
 impl<S> Router<S> {
     fn new(server: Server, svc: S) -> Self
     where
-        S: Service<Request<Body>, Response = Response<Body>>
+        S: NamedService
+            + Service<Request<Body>, Response = Response<Body>>
             + Clone 
             + 'static,
         S::Future: Send + 'static,
@@ -67,7 +319,41 @@ impl<S> Router<S> {
     {
         Router {
             server: server,
-            routes: Routes::new(svc)
+            routes: Routes::new(svc),
+            names: vec![S::NAME],
+        }
+    }
+
+    pub fn add_service<S2>(self, svc: S2) -> Router<Or<S2, S>>
+    where
+        S2: NamedService
+            + Service<Request<Body>, Response = Response<Body>, Error = S::Error>
+            + Clone
+            + 'static,
+        S2::Future: Send + 'static,
+        S: Service<Request<Body>, Response = Response<Body>>,
+        S::Future: Send + 'static,
+    {
+        let mut names = self.names;
+        names.push(S2::NAME);
+        Router {
+            server: self.server,
+            routes: self.routes.push(svc),
+            names,
+        }
+    }
+
+    pub fn fallback<F>(self, fallback: F) -> Router<Fallback<S, F>>
+    where
+        F: FallbackService + Clone + Send + 'static,
+        S: Service<Request<Body>, Response = Response<Body>>,
+        S::Future: Send + 'static,
+    {
+        let routes = Fallback::new(self.names.clone(), self.routes.inner, fallback);
+        Router {
+            server: self.server,
+            routes: Routes::new(routes),
+            names: self.names,
         }
     }
 
@@ -83,6 +369,53 @@ impl<S> Router<S> {
     {
         self.server.serve(listener, self.routes).await
     }
+
+    pub async fn serve_with_shutdown<F>(self, listener: Async<TcpListener>, signal: F) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        F: Future<Output = ()>,
+    {
+        self.server.serve_with_shutdown(listener, self.routes, signal).await
+    }
+
+    pub async fn serve_with_incoming<I, IO, IE>(self, incoming: I) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        IE: Into<common::Error>,
+    {
+        self.server.serve_with_incoming(incoming, self.routes).await
+    }
+
+    pub async fn serve_with_incoming_shutdown<I, IO, IE, F>(self, incoming: I, signal: F) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        IE: Into<common::Error>,
+        F: Future<Output = ()>,
+    {
+        self.server.serve_with_incoming_shutdown(incoming, self.routes, signal).await
+    }
 }
 
 #[derive(Default, Clone)]
@@ -97,8 +430,13 @@ impl<S> Routes<S> {
         }
     }
 
-    async fn handle(mut self, socket: Async<TcpStream>) -> Result<(), Box<dyn Error + Send + Sync>>
+    fn push<S2: NamedService>(self, svc: S2) -> Routes<Or<S2, S>> {
+        Routes::new(Or::new(S2::NAME, svc, self.inner))
+    }
+
+    async fn handle<IO>(self, socket: IO, server: Server, mut watcher: Watcher) -> Result<(), Box<dyn Error + Send + Sync>>
     where
+        IO: AsyncRead + AsyncWrite + Unpin,
         S: Service<Request<Body>, Response = Response<Body>>
             + Clone 
             + Send 
@@ -107,39 +445,297 @@ impl<S> Routes<S> {
         S::Future: Send + 'static,
         <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
     {
-        let mut connection = server::handshake(socket).await?;
-    
-        while let Some(result) = connection.accept().await {
-            let (mut request, mut respond) = result?;
-    
-            let body = request.body_mut();
-
-            while let Some(data) = body.data().await {
-                
-                let data = data?;
-                let datalen = data.len();
-
-                let gd = Request::new(Body::new(Some(data)));
-                let output = self.inner.call(gd).await?;
-
-                let _ = body.flow_control().release_capacity(datalen);
-                let output_bytes = output.into_inner().data();
-                match output_bytes {
-                    Some(data) => {
-                        let hresponse = http::Response::new(());
-                        let mut send = respond.send_response(hresponse, false)?;
-                        send.send_data(data, false)?;
-            
-                        let mut trailers = HeaderMap::new();
-                        trailers.insert("zomg", "hello".parse().unwrap());
-            
-                        send.send_trailers(trailers).unwrap();
+        let (socket, too_many_pings) = PingPolicy::new(socket, server.min_ping_interval);
+        let (socket, received) = Recorded::new(socket);
+
+        // a client that never sends its preface must not hold up a shutdown,
+        // so the drain signal is watched from the start
+        let mut handshake = Box::pin(server.http2.server().handshake(socket));
+        let mut draining = false;
+        let mut connection = loop {
+            let step = future::poll_fn(|cx| {
+                if let Poll::Ready(signal) = watcher.poll(cx) {
+                    return Poll::Ready(Err(signal));
+                }
+                handshake.as_mut().poll(cx).map(Ok)
+            })
+            .await;
+
+            match step {
+                Ok(connection) => break connection?,
+                Err(Signal::Drain) => draining = true,
+                Err(Signal::Close) => {
+                    debug!("closing connection, drain deadline elapsed during handshake");
+                    return Ok(());
+                }
+            }
+        };
+        if draining {
+            connection.graceful_shutdown();
+        }
+
+        let mut keepalive = server.keepalive.clone();
+        keepalive.bdp_initial_window = server.http2.bdp_initial_window();
+        let mut ponger = Ponger::new(connection.ping_pong(), &keepalive, received);
+        let limit = server.limits.connection();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let mut lifetime = Lifetime::new(&server.lifetime);
+
+        loop {
+            let event = future::poll_fn(|cx| {
+                if too_many_pings.load(Ordering::Acquire) {
+                    return Poll::Ready(Event::TooManyPings);
+                }
+
+                if let Poll::Ready(signal) = watcher.poll(cx) {
+                    return Poll::Ready(Event::Shutdown(signal));
+                }
+
+                let is_idle = in_flight.load(Ordering::Acquire) == 0;
+                if let Poll::Ready(expired) = lifetime.poll(cx, is_idle) {
+                    return Poll::Ready(Event::Expired(expired));
+                }
+
+                if let Some(ponger) = ponger.as_mut() {
+                    if let Poll::Ready(ponged) = ponger.poll(cx, is_idle) {
+                        return Poll::Ready(Event::Ponged(ponged));
                     }
-                    None => {}
+                }
+
+                // pings are only sniffed while h2 reads, which happens in here,
+                // and nothing wakes the loop again once one went over the limit
+                let accepted = connection.poll_accept(cx).map(Event::Accept);
+                if accepted.is_pending() && too_many_pings.load(Ordering::Acquire) {
+                    return Poll::Ready(Event::TooManyPings);
+                }
+                accepted
+            })
+            .await;
+
+            match event {
+                Event::Accept(Some(result)) => {
+                    let (request, respond) = result?;
+
+                    let mut routes = self.clone();
+                    let limit = limit.clone();
+                    let message_sizes = server.message_sizes;
+                    let in_flight = InFlight::new(&in_flight);
+                    runtime::spawn(async move {
+                        let _in_flight = in_flight;
+                        let result = match limit.acquire().await {
+                            Ok(_permit) => routes.process(request, respond, message_sizes).await,
+                            Err(status) => send_trailers_only(respond, HeaderMap::new(), ContentType::default(), status),
+                        };
+                        if let Err(e) = result {
+                            debug!("stream error: {}", e);
+                        }
+                    })
+                    .detach();
+                }
+                Event::Accept(None) => break,
+                Event::Ponged(Ponged::SizeUpdate(size)) => update_window(&mut connection, size),
+                Event::Ponged(Ponged::KeepAliveTimedOut) => {
+                    debug!("closing connection, keepalive timed out");
+                    break;
+                }
+                Event::Expired(Expired::Age) => {
+                    debug!("draining connection, max age reached");
+                    connection.graceful_shutdown();
+                }
+                Event::Expired(Expired::Idle) => {
+                    debug!("draining connection, max idle reached");
+                    connection.graceful_shutdown();
+                }
+                Event::Expired(Expired::Grace) => {
+                    debug!("closing connection, max age grace elapsed");
+                    break;
+                }
+                Event::Shutdown(Signal::Drain) => {
+                    debug!("draining connection");
+                    connection.graceful_shutdown();
+                }
+                Event::Shutdown(Signal::Close) => {
+                    debug!("closing connection, drain deadline elapsed");
+                    break;
+                }
+                Event::TooManyPings => {
+                    debug!("closing connection, too many pings");
+                    connection.abrupt_shutdown(Reason::ENHANCE_YOUR_CALM);
+                    let _ = future::poll_fn(|cx| connection.poll_closed(cx)).await;
+                    break;
                 }
             }
         }
 
         Ok(())
     }
+
+    async fn process(&mut self, request: http::Request<RecvStream>, mut respond: SendResponse<Bytes>, message_sizes: MessageSizes) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>
+            + Clone 
+            + Send 
+            + Sync 
+            + 'static,
+        S::Future: Send + 'static,
+        <S as Service<Request<Body>>>::Error: Send + Sync + std::error::Error,
+    {
+        if let Err((http_status, status)) = validate(&request) {
+            debug!("rejecting request: {}", status.message());
+            return send_rejection(respond, http_status, status);
+        }
+
+        let path = request.uri().path_and_query().cloned();
+        let content_type = request.headers().get(CONTENT_TYPE).cloned();
+
+        let mut gd = Request::from_http(request.map(Body::h2));
+        if let Some(path) = path {
+            gd.extensions_mut().insert(path);
+        }
+        if let Some(content_type) = content_type {
+            gd.extensions_mut().insert(ContentType(content_type));
+        }
+        gd.extensions_mut().insert(message_sizes);
+
+        // a client that cancels the call resets the stream, the handler is
+        // dropped then and gives back its concurrency permit
+        let call = async { Some(recover::call(self.inner.call(gd)).await) };
+        let reset = async {
+            let _ = future::poll_fn(|cx| respond.poll_reset(cx)).await;
+            None
+        };
+        let output = match future::or(call, reset).await {
+            Some(output) => output?,
+            None => {
+                debug!("call cancelled by the client");
+                return Ok(());
+            }
+        };
+        send_response(respond, output).await
+    }
+}
+
+async fn send_response(mut respond: SendResponse<Bytes>, mut output: Response<Body>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let content_type = output.extensions().get::<ContentType>().cloned().unwrap_or_default();
+    let mut trailers = mem::take(output.trailers_mut()).into_sanitized_headers();
+    let (metadata, mut body) = output.into_parts();
+    let mut headers = metadata.into_sanitized_headers();
+
+    let mut data = match body::next(&mut body, |cx| respond.poll_reset(cx)).await {
+        Next::Data(data) => data,
+        Next::End => {
+            headers.extend(trailers);
+            return send_trailers_only(respond, headers, content_type, Status::ok(""));
+        }
+        Next::Failed(status) => {
+            headers.extend(trailers);
+            return send_trailers_only(respond, headers, content_type, status);
+        }
+        Next::Reset => return Ok(()),
+    };
+
+    let mut hresponse = http::Response::new(());
+    *hresponse.headers_mut() = headers;
+    hresponse
+        .headers_mut()
+        .insert(CONTENT_TYPE, content_type.0);
+    let mut send = respond.send_response(hresponse, false)?;
+
+    let status = loop {
+        body::send_data(&mut send, data, false).await?;
+
+        match body::next(&mut body, |cx| send.poll_reset(cx)).await {
+            Next::Data(next) => data = next,
+            Next::End => break Status::ok(""),
+            Next::Failed(status) => break status,
+            Next::Reset => return Ok(()),
+        }
+    };
+
+    status.add_header(&mut trailers)?;
+
+    send.send_trailers(trailers)?;
+
+    Ok(())
+}
+
+fn send_trailers_only(mut respond: SendResponse<Bytes>, headers: HeaderMap, content_type: ContentType, status: Status) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut response = http::Response::new(());
+    *response.headers_mut() = headers;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, content_type.0);
+    status.add_header(response.headers_mut())?;
+
+    respond.send_response(response, true)?;
+
+    Ok(())
+}
+
+// Checks what the gRPC over HTTP/2 spec requires of a request before any
+// service sees it.
+fn validate<B>(request: &http::Request<B>) -> Result<(), (StatusCode, Status)> {
+    if request.method() != Method::POST {
+        let message = format!("method {} is not allowed, gRPC requires POST", request.method());
+        return Err((StatusCode::METHOD_NOT_ALLOWED, Status::internal(message)));
+    }
+
+    let content_type = request.headers().get(CONTENT_TYPE).map(HeaderValue::as_bytes);
+    if !content_type.is_some_and(ContentType::is_grpc) {
+        let message = format!(
+            "invalid gRPC request content-type {:?}",
+            String::from_utf8_lossy(content_type.unwrap_or_default())
+        );
+        return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, Status::internal(message)));
+    }
+
+    if request.headers().get(TE).map(HeaderValue::as_bytes) != Some(b"trailers") {
+        let message = "missing te: trailers header";
+        return Err((StatusCode::BAD_REQUEST, Status::internal(message)));
+    }
+
+    // the path is `/<service>/<method>`
+    let path = request.uri().path();
+    let is_method = match path.strip_prefix('/').and_then(|path| path.split_once('/')) {
+        Some((service, method)) => !service.is_empty() && !method.is_empty() && !method.contains('/'),
+        None => false,
+    };
+    if !is_method {
+        let message = format!("malformed method name {:?}", path);
+        return Err((StatusCode::OK, Status::unimplemented(message)));
+    }
+
+    Ok(())
+}
+
+fn send_rejection(mut respond: SendResponse<Bytes>, http_status: StatusCode, status: Status) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if http_status == StatusCode::OK {
+        return send_trailers_only(respond, HeaderMap::new(), ContentType::default(), status);
+    }
+
+    let mut response = http::Response::new(());
+    *response.status_mut() = http_status;
+    status.add_header(response.headers_mut())?;
+
+    respond.send_response(response, true)?;
+
+    Ok(())
+}
+
+fn update_window<T>(connection: &mut server::Connection<T, Bytes>, size: u32)
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    connection.set_target_window_size(size);
+    if let Err(e) = connection.set_initial_window_size(size) {
+        debug!("failed to update window size: {}", e);
+    }
+}
+
+enum Event<T> {
+    Accept(Option<T>),
+    Ponged(Ponged),
+    Expired(Expired),
+    Shutdown(Signal),
+    TooManyPings,
 }
@@ -0,0 +1,165 @@
+use bytes::Bytes;
+use cynthia::runtime::stream::Stream;
+use http::uri::PathAndQuery;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::codec::{BytesCodec, Streaming};
+use crate::common::{Body, Never, Request, Response};
+use crate::controller::server::Rpc;
+use crate::runtime::{BoxFuture, Service};
+use crate::Status;
+
+pub trait NamedService {
+    const NAME: &'static str;
+}
+
+// Sends calls for the service called `name` to `a` and everything else to `b`.
+#[derive(Clone, Debug)]
+pub struct Or<A, B> {
+    name: &'static str,
+    a: A,
+    b: B,
+}
+
+impl<A, B> Or<A, B> {
+    pub(crate) fn new(name: &'static str, a: A, b: B) -> Self {
+        Or { name, a, b }
+    }
+}
+
+impl<A, B> Service<Request<Body>> for Or<A, B>
+where
+    A: Service<Request<Body>, Response = Response<Body>>,
+    A::Future: Send + 'static,
+    B: Service<Request<Body>, Response = Response<Body>, Error = A::Error>,
+    B::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = A::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let matched = req
+            .extensions()
+            .get::<PathAndQuery>()
+            .map(|path| service_name(path.path()) == Some(self.name))
+            .unwrap_or(false);
+
+        if matched {
+            Box::pin(self.a.call(req))
+        } else {
+            Box::pin(self.b.call(req))
+        }
+    }
+}
+
+// Handles the calls no registered service is named for, with the payloads of
+// their messages left undecoded.
+pub trait FallbackService {
+    type ResponseStream: Stream<Item = Result<Bytes, Status>> + Send + 'static;
+    type Future: Future<Output = Result<Response<Self::ResponseStream>, Status>> + Send + 'static;
+    fn call(&mut self, path: PathAndQuery, request: Request<Streaming<Bytes>>) -> Self::Future;
+}
+
+impl<F, Fut, S> FallbackService for F
+where
+    F: FnMut(PathAndQuery, Request<Streaming<Bytes>>) -> Fut,
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: Stream<Item = Result<Bytes, Status>> + Send + 'static,
+{
+    type ResponseStream = S;
+    type Future = Fut;
+
+    fn call(&mut self, path: PathAndQuery, request: Request<Streaming<Bytes>>) -> Self::Future {
+        self(path, request)
+    }
+}
+
+// Sends calls for the services in `names` to `routes` and everything else
+// to `fallback`.
+#[derive(Clone, Debug)]
+pub struct Fallback<S, F> {
+    names: Arc<Vec<&'static str>>,
+    routes: S,
+    fallback: F,
+}
+
+impl<S, F> Fallback<S, F> {
+    pub(crate) fn new(names: Vec<&'static str>, routes: S, fallback: F) -> Self {
+        Fallback {
+            names: Arc::new(names),
+            routes,
+            fallback,
+        }
+    }
+}
+
+impl<S, F> Service<Request<Body>> for Fallback<S, F>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Future: Send + 'static,
+    F: FallbackService + Clone + Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let path = req.extensions().get::<PathAndQuery>().cloned();
+        let matched = path
+            .as_ref()
+            .and_then(|path| service_name(path.path()))
+            .map(|name| self.names.contains(&name))
+            .unwrap_or(false);
+
+        if matched {
+            return Box::pin(self.routes.call(req));
+        }
+
+        let handler = WithPath {
+            path: path.unwrap_or_else(|| PathAndQuery::from_static("/")),
+            fallback: self.fallback.clone(),
+        };
+        Box::pin(async move { Ok(Rpc::new(BytesCodec).streaming(handler, req).await) })
+    }
+}
+
+// Answers every call with `Unimplemented`; the routes of a router that
+// only has a fallback.
+#[derive(Clone, Debug, Default)]
+pub struct Unimplemented;
+
+impl Service<Request<Body>> for Unimplemented {
+    type Response = Response<Body>;
+    type Error = Never;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn call(&mut self, _: Request<Body>) -> Self::Future {
+        let status = Status::unimplemented("method not implemented");
+        Box::pin(async move { Ok(Response::new(Body::error(status))) })
+    }
+}
+
+// Adapts a fallback to the streaming service the server `Rpc` drives.
+struct WithPath<F> {
+    path: PathAndQuery,
+    fallback: F,
+}
+
+impl<F: FallbackService> Service<Request<Streaming<Bytes>>> for WithPath<F> {
+    type Response = Response<F::ResponseStream>;
+    type Error = Status;
+    type Future = F::Future;
+
+    fn call(&mut self, req: Request<Streaming<Bytes>>) -> Self::Future {
+        self.fallback.call(self.path.clone(), req)
+    }
+}
+
+fn service_name(path: &str) -> Option<&str> {
+    let mut segments = path.strip_prefix('/')?.splitn(2, '/');
+    let service = segments.next()?;
+    segments.next()?;
+    Some(service)
+}
@@ -0,0 +1,140 @@
+use nephele::proto::h2::{client, server};
+use socket2::{SockRef, TcpKeepalive};
+use std::io;
+use std::net::TcpStream;
+use std::time::Duration;
+
+use super::ping::DEFAULT_WINDOW_SIZE;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Http2 {
+    pub(crate) initial_stream_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+    pub(crate) adaptive_window: bool,
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) max_frame_size: Option<u32>,
+    pub(crate) max_header_list_size: Option<u32>,
+}
+
+impl Http2 {
+    // The adaptive window starts from the spec defaults and grows from there,
+    // so explicit window sizes are ignored once it is enabled.
+    fn windows(&self) -> (Option<u32>, Option<u32>) {
+        if self.adaptive_window {
+            (Some(DEFAULT_WINDOW_SIZE), Some(DEFAULT_WINDOW_SIZE))
+        } else {
+            (
+                self.initial_stream_window_size,
+                self.initial_connection_window_size,
+            )
+        }
+    }
+
+    pub(crate) fn bdp_initial_window(&self) -> Option<u32> {
+        if self.adaptive_window {
+            Some(DEFAULT_WINDOW_SIZE)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn server(&self) -> server::Builder {
+        let mut builder = server::Builder::new();
+        let (stream_window, connection_window) = self.windows();
+
+        if let Some(size) = stream_window {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = connection_window {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(max) = self.max_frame_size {
+            builder.max_frame_size(max);
+        }
+        if let Some(max) = self.max_header_list_size {
+            builder.max_header_list_size(max);
+        }
+
+        builder
+    }
+
+    pub(crate) fn client(&self) -> client::Builder {
+        let mut builder = client::Builder::new();
+        let (stream_window, connection_window) = self.windows();
+
+        if let Some(size) = stream_window {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = connection_window {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(max) = self.max_frame_size {
+            builder.max_frame_size(max);
+        }
+        if let Some(max) = self.max_header_list_size {
+            builder.max_header_list_size(max);
+        }
+
+        builder
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tcp {
+    pub(crate) nodelay: bool,
+    pub(crate) keepalive: Option<Duration>,
+}
+
+impl Tcp {
+    pub(crate) fn apply(&self, socket: &TcpStream) -> io::Result<()> {
+        if self.nodelay {
+            socket.set_nodelay(true)?;
+        }
+
+        if let Some(idle) = self.keepalive {
+            let keepalive = TcpKeepalive::new().with_time(idle);
+            SockRef::from(socket).set_tcp_keepalive(&keepalive)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tcp;
+    use socket2::SockRef;
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    #[test]
+    fn tcp_options_are_set_on_the_socket() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        let tcp = Tcp {
+            nodelay: true,
+            keepalive: Some(Duration::from_secs(30)),
+        };
+        tcp.apply(&socket).unwrap();
+
+        assert!(socket.nodelay().unwrap());
+        let socket = SockRef::from(&socket);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn default_tcp_options_leave_the_socket_alone() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        Tcp::default().apply(&socket).unwrap();
+
+        assert!(!socket.nodelay().unwrap());
+        assert!(!SockRef::from(&socket).keepalive().unwrap());
+    }
+}